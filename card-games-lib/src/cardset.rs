@@ -0,0 +1,219 @@
+use crate::model::{Card, Describe, Pile, Rank, Suit};
+use core::iter::FromIterator;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Sub, SubAssign};
+
+/// A set of standard cards packed into the bits of a `u64`, for search code
+/// that needs cheap copies and constant time membership.
+///
+/// Card `Card(rank, suit)` is stored at bit `rank.index() * 4 +
+/// suit.index()`, so iteration runs through the ranks in `Rank::ALL` order
+/// and through the suits of each rank in `Suit::ALL` order. Smaller decks are
+/// subsets of the 52 cards, e.g. `Deck::schnapsen().into_iter().collect()`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub const EMPTY: CardSet = CardSet(0);
+    pub const FULL: CardSet = CardSet((1 << 52) - 1);
+
+    fn bit(card: Card) -> u64 {
+        1 << (card.rank().index() * 4 + card.suit().index())
+    }
+
+    fn card(bit: u32) -> Card {
+        let bit = bit as usize;
+        Card(Rank::ALL[bit / 4], Suit::ALL[bit % 4])
+    }
+
+    /// Bits above the 52 cards are dropped.
+    pub fn from_bits(bits: u64) -> CardSet {
+        CardSet(bits & Self::FULL.0)
+    }
+
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn of_suit(suit: Suit) -> CardSet {
+        CardSet(0x0001_1111_1111_1111 << suit.index())
+    }
+
+    pub fn of_rank(rank: Rank) -> CardSet {
+        CardSet(0xf << (rank.index() * 4))
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, card: Card) -> bool {
+        self.0 & Self::bit(card) != 0
+    }
+
+    /// Returns whether the card was newly added.
+    pub fn insert(&mut self, card: Card) -> bool {
+        let added = !self.contains(card);
+        self.0 |= Self::bit(card);
+        added
+    }
+
+    /// Returns whether the card was in the set.
+    pub fn remove(&mut self, card: Card) -> bool {
+        let removed = self.contains(card);
+        self.0 &= !Self::bit(card);
+        removed
+    }
+
+    pub fn union(self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    /// Every card of the 52 that is not in the set.
+    pub fn complement(self) -> CardSet {
+        CardSet(!self.0 & Self::FULL.0)
+    }
+
+    pub fn is_subset(self, other: CardSet) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    /// The cards of the set in the given suit.
+    pub fn suit(self, suit: Suit) -> CardSet {
+        self & Self::of_suit(suit)
+    }
+
+    pub fn iter(self) -> Iter {
+        Iter(self.0)
+    }
+
+    pub fn to_pile(self) -> Pile<Card> {
+        Pile::from_vec(self.iter().collect())
+    }
+}
+
+/// The cards of a `CardSet`, in bit order.
+#[derive(Debug, Clone)]
+pub struct Iter(u64);
+
+impl Iterator for Iter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            None
+        } else {
+            let bit = self.0.trailing_zeros();
+            self.0 &= self.0 - 1;
+            Some(CardSet::card(bit))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Iter {}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I>(cards: I) -> CardSet
+    where
+        I: IntoIterator<Item = Card>,
+    {
+        let mut set = CardSet::EMPTY;
+        set.extend(cards);
+        set
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I>(&mut self, cards: I)
+    where
+        I: IntoIterator<Item = Card>,
+    {
+        for card in cards {
+            self.insert(card);
+        }
+    }
+}
+
+impl<T> From<&Pile<T>> for CardSet
+where
+    T: Describe,
+{
+    fn from(pile: &Pile<T>) -> CardSet {
+        pile.iter().map(Describe::description).collect()
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = CardSet;
+
+    fn bitor(self, other: CardSet) -> CardSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = CardSet;
+
+    fn bitand(self, other: CardSet) -> CardSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for CardSet {
+    type Output = CardSet;
+
+    fn sub(self, other: CardSet) -> CardSet {
+        self.difference(other)
+    }
+}
+
+impl Not for CardSet {
+    type Output = CardSet;
+
+    fn not(self) -> CardSet {
+        self.complement()
+    }
+}
+
+impl BitOrAssign for CardSet {
+    fn bitor_assign(&mut self, other: CardSet) {
+        *self = *self | other
+    }
+}
+
+impl BitAndAssign for CardSet {
+    fn bitand_assign(&mut self, other: CardSet) {
+        *self = *self & other
+    }
+}
+
+impl SubAssign for CardSet {
+    fn sub_assign(&mut self, other: CardSet) {
+        *self = *self - other
+    }
+}
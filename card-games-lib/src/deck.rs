@@ -0,0 +1,146 @@
+use crate::model::{Card, Joker, Pile, Rank, Suit};
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+/// An ordered stack of cards. The top of the deck is the first card, and
+/// every draw or deal takes from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck<T>(Vec<T>);
+
+impl<T> Default for Deck<T> {
+    fn default() -> Deck<T> {
+        Deck(vec![])
+    }
+}
+
+impl<T> Deck<T>
+where
+    T: From<Card>,
+{
+    pub fn from_ranks(ranks: &[Rank]) -> Deck<T> {
+        Deck(
+            Suit::ALL
+                .iter()
+                .flat_map(|suit| ranks.iter().map(move |rank| Card(*rank, *suit).into()))
+                .collect(),
+        )
+    }
+
+    /// The 52 card French deck.
+    pub fn standard() -> Deck<T> {
+        Self::from_ranks(&Rank::ALL)
+    }
+
+    /// The 32 card piquet deck, sevens through aces.
+    pub fn piquet() -> Deck<T> {
+        use Rank::*;
+        Self::from_ranks(&[Seven, Eight, Nine, Ten, Jack, Queen, King, Ace])
+    }
+
+    /// The 24 card deck used for tysiac and Schnapsen, nines through aces.
+    pub fn schnapsen() -> Deck<T> {
+        use Rank::*;
+        Self::from_ranks(&[Nine, Ten, Jack, Queen, King, Ace])
+    }
+
+    /// The 54 card deck: the standard deck followed by the red and black
+    /// jokers.
+    pub fn with_jokers() -> Deck<T>
+    where
+        T: From<Joker>,
+    {
+        let mut deck = Self::standard();
+        deck.0.push(Joker::Red.into());
+        deck.0.push(Joker::Black.into());
+        deck
+    }
+}
+
+impl<T> Deck<T> {
+    pub fn from_vec(v: Vec<T>) -> Deck<T> {
+        Deck(v)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.0.iter()
+    }
+
+    pub fn drain(&mut self) -> vec::Drain<'_, T> {
+        self.0.drain(..)
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Deck<U> {
+        Deck(self.0.into_iter().map(f).collect())
+    }
+
+    pub fn shuffle<R>(&mut self, rng: &mut R)
+    where
+        R: Rng,
+    {
+        self.0.shuffle(rng)
+    }
+
+    /// Shuffles the deck so that the same seed always gives the same order.
+    pub fn shuffle_seeded(&mut self, seed: u64) {
+        self.shuffle(&mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Moves the top `at` cards to the bottom of the deck.
+    ///
+    /// Panics if `at` is greater than the number of cards in the deck.
+    pub fn cut(&mut self, at: usize) {
+        self.0.rotate_left(at)
+    }
+
+    pub fn draw(&mut self) -> Option<T> {
+        if self.0.is_empty() {
+            None
+        } else {
+            Some(self.0.remove(0))
+        }
+    }
+
+    /// Takes up to `count` cards off the top of the deck.
+    pub fn deal(&mut self, count: usize) -> Pile<T> {
+        let count = count.min(self.0.len());
+        Pile::from_vec(self.0.drain(..count).collect())
+    }
+
+    /// Deals one card at a time to each hand in turn until every hand has
+    /// received `per_hand` more cards.
+    pub fn deal_round_robin(&mut self, hands: &mut [Pile<T>], per_hand: usize) {
+        for _ in 0..per_hand {
+            self.deal_packets(hands, &[1]);
+        }
+    }
+
+    /// Deals in packets: for every entry of `packets` each hand in turn
+    /// receives that many cards, so `&[2, 3, 2]` is the usual 2-3-2 deal.
+    ///
+    /// Dealing stops early if the deck runs out.
+    pub fn deal_packets(&mut self, hands: &mut [Pile<T>], packets: &[usize]) {
+        for packet in packets {
+            for hand in hands.iter_mut() {
+                hand.extend(self.deal(*packet).drain());
+            }
+        }
+    }
+}
+
+impl<T> IntoIterator for Deck<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
@@ -0,0 +1,155 @@
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::Write;
+
+/// The states and transitions declared in a `game_states!` invocation, as
+/// returned by `Machine::graph` for the generated `State`. The first state
+/// declared is taken to be the initial state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    pub states: Vec<&'static str>,
+    pub transitions: Vec<Transition>,
+    branches: Vec<(&'static str, Vec<&'static str>)>,
+}
+
+/// A step or action of a state. Any input may also leave the game in `from`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub from: &'static str,
+    /// The name of the `StateInput` variant.
+    pub input: &'static str,
+    pub args: Vec<&'static str>,
+    pub to: Vec<&'static str>,
+    pub error: &'static str,
+}
+
+impl Graph {
+    pub fn new(states: &[&'static str], branches: &[(&'static str, &[&'static str])]) -> Graph {
+        Graph {
+            states: states.to_vec(),
+            transitions: vec![],
+            branches: branches
+                .iter()
+                .map(|(branch, states)| (*branch, states.to_vec()))
+                .collect(),
+        }
+    }
+
+    /// Adds a transition to `next`, which is either a state or a branch.
+    pub fn transition(
+        &mut self,
+        from: &'static str,
+        input: &'static str,
+        args: &[&'static str],
+        next: &'static str,
+        error: &'static str,
+    ) {
+        let to = self
+            .branches
+            .iter()
+            .find(|(branch, _)| *branch == next)
+            .map(|(_, states)| states.clone())
+            .unwrap_or_else(|| vec![next]);
+
+        self.transitions.push(Transition {
+            from,
+            input,
+            args: args.to_vec(),
+            to,
+            error,
+        })
+    }
+
+    pub fn initial(&self) -> Option<&'static str> {
+        self.states.first().copied()
+    }
+
+    /// The states that can be reached from the initial state, in the order
+    /// they are first reached.
+    pub fn reachable(&self) -> Vec<&'static str> {
+        let mut reached: Vec<&'static str> = self.initial().into_iter().collect();
+        let mut queue: VecDeque<&'static str> = reached.iter().copied().collect();
+
+        while let Some(state) = queue.pop_front() {
+            for transition in self.transitions.iter().filter(|t| t.from == state) {
+                for next in transition.to.iter() {
+                    if !reached.contains(next) {
+                        reached.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        reached
+    }
+
+    pub fn unreachable(&self) -> Vec<&'static str> {
+        let reached = self.reachable();
+        self.states
+            .iter()
+            .copied()
+            .filter(|state| !reached.contains(state))
+            .collect()
+    }
+
+    /// Renders the graph in Graphviz DOT, with an edge labelled by the
+    /// transition for every state it can move to.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        for state in self.states.iter() {
+            writeln!(dot, "    \"{}\";", state).expect("Writing to a String");
+        }
+        for (transition, next) in self.edges() {
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                transition.from,
+                next,
+                transition.label().replace('"', "\\\"")
+            )
+            .expect("Writing to a String");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a Mermaid state diagram.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("stateDiagram-v2\n");
+        if let Some(initial) = self.initial() {
+            writeln!(mermaid, "    [*] --> {}", initial).expect("Writing to a String");
+        }
+        for (transition, next) in self.edges() {
+            let label = transition
+                .label()
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+                .replace(':', "#58;");
+            writeln!(mermaid, "    {} --> {}: {}", transition.from, next, label)
+                .expect("Writing to a String");
+        }
+        mermaid
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (&Transition, &'static str)> {
+        self.transitions
+            .iter()
+            .flat_map(|transition| transition.to.iter().map(move |next| (transition, *next)))
+    }
+}
+
+impl Transition {
+    /// The input with its argument types and the error it fails with, e.g.
+    /// `Bidding(Option<Fives>) -> Err(String)`.
+    pub fn label(&self) -> String {
+        format!(
+            "{}({}) -> Err({})",
+            self.input,
+            self.args.join(", "),
+            self.error
+        )
+    }
+}
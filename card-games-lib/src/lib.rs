@@ -1,5 +1,32 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod cardset;
+pub mod deck;
+pub mod graph;
 pub mod model;
+pub mod notation;
+pub mod order;
+pub mod seats;
 pub mod states;
+pub mod trick;
 
+pub use cardset::CardSet;
+pub use deck::*;
+pub use graph::*;
 pub use model::*;
+pub use notation::*;
+pub use order::*;
+pub use seats::*;
 pub use states::*;
+pub use trick::*;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+    pub use either::Either;
+}
+
+#[cfg(test)]
+mod tests;
@@ -1,4 +1,10 @@
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+use crate::order::RankOrder;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::{seq::SliceRandom, Rng};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Rank {
     Ace,
     Two,
@@ -15,7 +21,7 @@ pub enum Rank {
     King,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Suit {
     Diamonds,
     Clubs,
@@ -23,12 +29,80 @@ pub enum Suit {
     Spades,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Card(pub Rank, pub Suit);
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Joker {
+    Red,
+    Black,
+}
+
+/// A card from a deck that may hold jokers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum PlayingCard {
+    Card(Card),
+    Joker(Joker),
+}
+
+/// A description that matches more than one card when searching a pile.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Wild {
+    Any,
+    Rank(Rank),
+    Suit(Suit),
+    Joker,
+    /// The given card, or a joker standing in for it.
+    OrJoker(Card),
+}
+
+/// A hand, stock or discard pile. Like a `Deck`, the top of the pile is the
+/// first card.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Pile<T>(Vec<T>);
 
+/// Why `Pile::try_extract` could not take a card. `index` is the position
+/// of the failing description in the request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractError<D> {
+    /// No card in the pile matches the description.
+    Missing { index: usize, description: D },
+    /// Every matching card is needed by an earlier description.
+    Duplicate { index: usize, description: D },
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Ace,
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+    ];
+
+    /// The position of the rank in `Rank::ALL`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Diamonds, Suit::Clubs, Suit::Hearts, Suit::Spades];
+
+    /// The position of the suit in `Suit::ALL`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
 impl Card {
     pub fn rank(self) -> Rank {
         self.0
@@ -38,6 +112,85 @@ impl Card {
     }
 }
 
+/// Anything that stands for one of the 52 standard cards.
+pub trait Describe {
+    fn description(&self) -> Card;
+}
+
+impl Describe for Card {
+    fn description(&self) -> Card {
+        *self
+    }
+}
+
+impl PlayingCard {
+    pub fn card(self) -> Option<Card> {
+        match self {
+            PlayingCard::Card(card) => Some(card),
+            PlayingCard::Joker(_) => None,
+        }
+    }
+
+    pub fn joker(self) -> Option<Joker> {
+        match self {
+            PlayingCard::Card(_) => None,
+            PlayingCard::Joker(joker) => Some(joker),
+        }
+    }
+
+    pub fn is_joker(self) -> bool {
+        self.joker().is_some()
+    }
+}
+
+impl From<Card> for PlayingCard {
+    fn from(card: Card) -> PlayingCard {
+        PlayingCard::Card(card)
+    }
+}
+
+impl From<Joker> for PlayingCard {
+    fn from(joker: Joker) -> PlayingCard {
+        PlayingCard::Joker(joker)
+    }
+}
+
+impl PartialEq<PlayingCard> for Card {
+    fn eq(&self, other: &PlayingCard) -> bool {
+        other.card() == Some(*self)
+    }
+}
+
+impl PartialEq<PlayingCard> for Joker {
+    fn eq(&self, other: &PlayingCard) -> bool {
+        other.joker() == Some(*self)
+    }
+}
+
+impl PartialEq<Card> for Wild {
+    fn eq(&self, other: &Card) -> bool {
+        match self {
+            Wild::Any => true,
+            Wild::Rank(rank) => other.rank() == *rank,
+            Wild::Suit(suit) => other.suit() == *suit,
+            Wild::Joker => false,
+            Wild::OrJoker(card) => card == other,
+        }
+    }
+}
+
+impl PartialEq<PlayingCard> for Wild {
+    fn eq(&self, other: &PlayingCard) -> bool {
+        match other {
+            PlayingCard::Card(card) => self == card,
+            PlayingCard::Joker(_) => match self {
+                Wild::Any | Wild::Joker | Wild::OrJoker(_) => true,
+                Wild::Rank(_) | Wild::Suit(_) => false,
+            },
+        }
+    }
+}
+
 impl<T> Default for Pile<T> {
     fn default() -> Pile<T> {
         Pile(vec![])
@@ -47,18 +200,49 @@ impl<T> Default for Pile<T> {
 impl<T> Extend<T> for Pile<T> {
     fn extend<U>(&mut self, cards: U)
     where
-        U: std::iter::IntoIterator<Item = T>,
+        U: IntoIterator<Item = T>,
     {
         self.0.extend(cards)
     }
 }
 
+/// Finds a card for `descriptions[index]`, moving cards already claimed by
+/// other descriptions to alternatives where needed.
+fn claim<T, D>(
+    cards: &[T],
+    descriptions: &[D],
+    index: usize,
+    owners: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool
+where
+    D: PartialEq<T>,
+{
+    for (card, c) in cards.iter().enumerate() {
+        if visited[card] || descriptions[index] != *c {
+            continue;
+        }
+        visited[card] = true;
+
+        let free = match owners[card] {
+            Some(other) => claim(cards, descriptions, other, owners, visited),
+            None => true,
+        };
+        if free {
+            owners[card] = Some(index);
+            return true;
+        }
+    }
+
+    false
+}
+
 impl<T> Pile<T> {
     pub fn from_vec(v: Vec<T>) -> Pile<T> {
         Pile(v)
     }
 
-    pub fn drain(&mut self) -> std::vec::Drain<'_, T> {
+    pub fn drain(&mut self) -> vec::Drain<'_, T> {
         self.0.drain(..)
     }
 
@@ -66,28 +250,65 @@ impl<T> Pile<T> {
         self.0.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn extract<D>(&mut self, descriptions: impl Iterator<Item = D>) -> Option<Vec<T>>
     where
         D: PartialEq<T>,
     {
-        let mut taken = std::collections::BTreeSet::new();
+        self.try_extract(descriptions).ok()
+    }
 
-        for desc in descriptions {
-            if let Some(idx) = self
-                .0
-                .iter()
-                .enumerate()
-                .filter(|(idx, c)| !taken.contains(idx) && desc == **c)
-                .map(|(idx, _)| idx)
-                .next()
-            {
-                taken.insert(idx);
-            } else {
-                return None;
+    /// Takes one distinct card for each description, returned in the order
+    /// of the descriptions. Cards are matched so that a wild description
+    /// never takes a card a later description needs. If any description
+    /// cannot be satisfied the pile is left untouched.
+    pub fn try_extract<D>(
+        &mut self,
+        descriptions: impl IntoIterator<Item = D>,
+    ) -> Result<Vec<T>, ExtractError<D>>
+    where
+        D: PartialEq<T>,
+    {
+        let descriptions: Vec<D> = descriptions.into_iter().collect();
+        let mut owners: Vec<Option<usize>> = vec![None; self.0.len()];
+
+        for index in 0..descriptions.len() {
+            let mut visited = vec![false; self.0.len()];
+            if !claim(&self.0, &descriptions, index, &mut owners, &mut visited) {
+                let description = descriptions
+                    .into_iter()
+                    .nth(index)
+                    .expect("The index is in range");
+                return Err(if self.contains(&description) {
+                    ExtractError::Duplicate { index, description }
+                } else {
+                    ExtractError::Missing { index, description }
+                });
             }
         }
 
-        Some(taken.iter().rev().map(|idx| self.0.remove(*idx)).collect())
+        let taken = (0..descriptions.len()).map(|index| {
+            owners
+                .iter()
+                .position(|owner| *owner == Some(index))
+                .expect("Every description claimed a card")
+        });
+
+        let mut removal: Vec<(usize, usize)> = taken.enumerate().collect();
+        removal.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut extracted: Vec<Option<T>> = removal.iter().map(|_| None).collect();
+        for (position, idx) in removal {
+            extracted[position] = Some(self.0.remove(idx));
+        }
+
+        Ok(extracted
+            .into_iter()
+            .map(|c| c.expect("Every description took a card"))
+            .collect())
     }
 
     pub fn get(&self, index: usize) -> Option<&T> {
@@ -125,6 +346,105 @@ impl<T> Pile<T> {
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.0.iter()
     }
+
+    pub fn shuffle<R>(&mut self, rng: &mut R)
+    where
+        R: Rng,
+    {
+        self.0.shuffle(rng)
+    }
+
+    /// Puts a card on top of the pile, where `add` puts it at the bottom.
+    pub fn put_on_top(&mut self, card: T) {
+        self.0.insert(0, card)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.0.first()
+    }
+
+    /// Takes up to `count` cards off the top of the pile, keeping their order.
+    pub fn take_top(&mut self, count: usize) -> Pile<T> {
+        let rest = self.split_at(count.min(self.0.len()));
+        core::mem::replace(self, rest)
+    }
+
+    /// Splits off the cards from `at` onwards, leaving the first `at` cards.
+    ///
+    /// Panics if `at` is greater than the number of cards in the pile.
+    pub fn split_at(&mut self, at: usize) -> Pile<T> {
+        Pile(self.0.split_off(at))
+    }
+
+    /// Panics if `index` is greater than the number of cards in the pile.
+    pub fn insert_at(&mut self, index: usize, card: T) {
+        self.0.insert(index, card)
+    }
+
+    /// Removes every card matching `desc` into a pile of its own.
+    pub fn split_off<D>(&mut self, desc: &D) -> Pile<T>
+    where
+        D: PartialEq<T>,
+    {
+        let (matching, rest) = self.0.drain(..).partition(|c| desc == c);
+        self.0 = rest;
+        Pile(matching)
+    }
+}
+
+impl<T> Pile<T>
+where
+    T: Describe,
+{
+    /// Sorts by suit, then from the strongest to the weakest rank.
+    pub fn sort<O>(&mut self, order: O)
+    where
+        O: RankOrder,
+    {
+        self.0.sort_by(|a, b| {
+            let (a, b) = (a.description(), b.description());
+            a.suit()
+                .cmp(&b.suit())
+                .then_with(|| order.compare(b.rank(), a.rank()))
+        })
+    }
+
+    pub fn group_by_suit(&self) -> BTreeMap<Suit, Vec<&T>> {
+        let mut groups = BTreeMap::new();
+        for card in self.0.iter() {
+            groups
+                .entry(card.description().suit())
+                .or_insert_with(Vec::new)
+                .push(card);
+        }
+        groups
+    }
+
+    pub fn count_suit(&self, suit: Suit) -> usize {
+        self.of_suit(suit).count()
+    }
+
+    pub fn highest_of<O>(&self, suit: Suit, order: O) -> Option<&T>
+    where
+        O: RankOrder,
+    {
+        self.of_suit(suit)
+            .max_by_key(|c| order.strength(c.description().rank()))
+    }
+
+    pub fn lowest_of<O>(&self, suit: Suit, order: O) -> Option<&T>
+    where
+        O: RankOrder,
+    {
+        self.of_suit(suit)
+            .min_by_key(|c| order.strength(c.description().rank()))
+    }
+
+    fn of_suit(&self, suit: Suit) -> impl Iterator<Item = &T> {
+        self.0
+            .iter()
+            .filter(move |c| c.description().suit() == suit)
+    }
 }
 
 #[macro_export]
@@ -137,20 +457,18 @@ macro_rules! pile_extract {
     };
 
 
-    ($pile:expr, $($x:expr),+ $(,)?) => {
+    (try $pile:expr, $($x:expr),+ $(,)?) => {
         {
             let pile = $pile;
 
-            let extracted = pile.extract(vec![$($x),+].into_iter());
-
-            if let Some(mut extracted) = extracted {
-                Some((
-                    pile_extract!(@vec_to_tuple_xs extracted ; ; $($x,)+)
-                ))
-            } else {
-                None
-            }
+            pile.try_extract($crate::__private::vec![$($x),+]).map(|mut extracted| {
+                pile_extract!(@vec_to_tuple_xs extracted ; ; $($x,)+)
+            })
         }
+    };
+
+    ($pile:expr, $($x:expr),+ $(,)?) => {
+        pile_extract!(try $pile, $($x),+).ok()
     }
 }
 
@@ -161,6 +479,6 @@ macro_rules! pile {
     );
 
     ($($x:expr),+ $(,)?) => (
-        Pile::from_vec(vec![ $($x),+ ])
+        $crate::Pile::from_vec($crate::__private::vec![ $($x),+ ])
     );
 }
@@ -0,0 +1,316 @@
+use crate::model::{Card, Joker, Pile, PlayingCard, Rank, Suit};
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+/// The styles cards can be written in. Every style can be parsed back.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Notation {
+    /// `TS`, `9C`, `RJ`
+    Ascii,
+    /// `10♠`, `9♣`, `🂿`
+    Unicode,
+    /// `Ten of Spades`, `Nine of Clubs`, `Red Joker`
+    Long,
+}
+
+/// Displays `.0` in the notation `.1`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Notated<T>(pub T, pub Notation);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    UnknownRank(String),
+    UnknownSuit(String),
+    UnknownCard(String),
+}
+
+impl Notation {
+    pub fn show<T>(self, value: T) -> Notated<T> {
+        Notated(value, self)
+    }
+}
+
+impl fmt::Display for Notated<Rank> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Notated(rank, notation) = *self;
+        let short = match rank {
+            Rank::Ace => "A",
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "T",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+        };
+
+        match notation {
+            Notation::Ascii => f.write_str(short),
+            Notation::Unicode if rank == Rank::Ten => f.write_str("10"),
+            Notation::Unicode => f.write_str(short),
+            Notation::Long => f.write_str(rank.name()),
+        }
+    }
+}
+
+impl fmt::Display for Notated<Suit> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Notated(suit, notation) = *self;
+        f.write_str(match (notation, suit) {
+            (Notation::Ascii, Suit::Diamonds) => "D",
+            (Notation::Ascii, Suit::Clubs) => "C",
+            (Notation::Ascii, Suit::Hearts) => "H",
+            (Notation::Ascii, Suit::Spades) => "S",
+            (Notation::Unicode, Suit::Diamonds) => "♦",
+            (Notation::Unicode, Suit::Clubs) => "♣",
+            (Notation::Unicode, Suit::Hearts) => "♥",
+            (Notation::Unicode, Suit::Spades) => "♠",
+            (Notation::Long, suit) => suit.name(),
+        })
+    }
+}
+
+impl fmt::Display for Notated<Card> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Notated(Card(rank, suit), notation) = *self;
+        let rank = Notated(rank, notation);
+        let suit = Notated(suit, notation);
+
+        match notation {
+            Notation::Ascii | Notation::Unicode => write!(f, "{}{}", rank, suit),
+            Notation::Long => write!(f, "{} of {}", rank, suit),
+        }
+    }
+}
+
+impl fmt::Display for Notated<Joker> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            Notated(Joker::Red, Notation::Ascii) => "RJ",
+            Notated(Joker::Black, Notation::Ascii) => "BJ",
+            Notated(Joker::Red, Notation::Unicode) => "🂿",
+            Notated(Joker::Black, Notation::Unicode) => "🃏",
+            Notated(Joker::Red, Notation::Long) => "Red Joker",
+            Notated(Joker::Black, Notation::Long) => "Black Joker",
+        })
+    }
+}
+
+impl fmt::Display for Notated<PlayingCard> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Notated(PlayingCard::Card(card), notation) => Notated(card, notation).fmt(f),
+            Notated(PlayingCard::Joker(joker), notation) => Notated(joker, notation).fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Notated(*self, Notation::Ascii).fmt(f)
+    }
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Notated(*self, Notation::Ascii).fmt(f)
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Notated(*self, Notation::Ascii).fmt(f)
+    }
+}
+
+impl fmt::Display for Joker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Notated(*self, Notation::Ascii).fmt(f)
+    }
+}
+
+impl fmt::Display for PlayingCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Notated(*self, Notation::Ascii).fmt(f)
+    }
+}
+
+/// Cards separated by spaces, as accepted by `Pile::from_str`.
+impl<T> fmt::Display for Pile<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, card) in self.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(" ")?;
+            }
+            card.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Rank {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rank::Ace => "Ace",
+            Rank::Two => "Two",
+            Rank::Three => "Three",
+            Rank::Four => "Four",
+            Rank::Five => "Five",
+            Rank::Six => "Six",
+            Rank::Seven => "Seven",
+            Rank::Eight => "Eight",
+            Rank::Nine => "Nine",
+            Rank::Ten => "Ten",
+            Rank::Jack => "Jack",
+            Rank::Queen => "Queen",
+            Rank::King => "King",
+        }
+    }
+}
+
+impl Suit {
+    pub fn name(self) -> &'static str {
+        match self {
+            Suit::Diamonds => "Diamonds",
+            Suit::Clubs => "Clubs",
+            Suit::Hearts => "Hearts",
+            Suit::Spades => "Spades",
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Rank, ParseError> {
+        let s = s.trim();
+        let short = match s.to_ascii_uppercase().as_str() {
+            "" => return Err(ParseError::Empty),
+            "A" => Some(Rank::Ace),
+            "2" => Some(Rank::Two),
+            "3" => Some(Rank::Three),
+            "4" => Some(Rank::Four),
+            "5" => Some(Rank::Five),
+            "6" => Some(Rank::Six),
+            "7" => Some(Rank::Seven),
+            "8" => Some(Rank::Eight),
+            "9" => Some(Rank::Nine),
+            "T" | "10" => Some(Rank::Ten),
+            "J" => Some(Rank::Jack),
+            "Q" => Some(Rank::Queen),
+            "K" => Some(Rank::King),
+            _ => None,
+        };
+
+        short
+            .or_else(|| {
+                Rank::ALL
+                    .iter()
+                    .copied()
+                    .find(|rank| rank.name().eq_ignore_ascii_case(s))
+            })
+            .ok_or_else(|| ParseError::UnknownRank(s.to_owned()))
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Suit, ParseError> {
+        let s = s.trim();
+        let short = match s.to_ascii_uppercase().as_str() {
+            "" => return Err(ParseError::Empty),
+            "D" | "♦" | "♢" => Some(Suit::Diamonds),
+            "C" | "♣" | "♧" => Some(Suit::Clubs),
+            "H" | "♥" | "♡" => Some(Suit::Hearts),
+            "S" | "♠" | "♤" => Some(Suit::Spades),
+            _ => None,
+        };
+
+        short
+            .or_else(|| {
+                Suit::ALL.iter().copied().find(|suit| {
+                    let name = suit.name();
+                    name.eq_ignore_ascii_case(s) || name[..name.len() - 1].eq_ignore_ascii_case(s)
+                })
+            })
+            .ok_or_else(|| ParseError::UnknownSuit(s.to_owned()))
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Card, ParseError> {
+        let s = s.trim();
+
+        if let Some(idx) = s.to_ascii_lowercase().find(" of ") {
+            return Ok(Card(s[..idx].parse()?, s[idx + 4..].parse()?));
+        }
+
+        let (split, _) = s.char_indices().last().ok_or(ParseError::Empty)?;
+        Ok(Card(s[..split].parse()?, s[split..].parse()?))
+    }
+}
+
+impl FromStr for Joker {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Joker, ParseError> {
+        [Joker::Red, Joker::Black]
+            .iter()
+            .copied()
+            .find(|joker| {
+                [Notation::Ascii, Notation::Unicode, Notation::Long]
+                    .iter()
+                    .any(|notation| {
+                        Notated(*joker, *notation)
+                            .to_string()
+                            .eq_ignore_ascii_case(s.trim())
+                    })
+            })
+            .ok_or_else(|| ParseError::UnknownCard(s.trim().to_owned()))
+    }
+}
+
+impl FromStr for PlayingCard {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<PlayingCard, ParseError> {
+        s.parse::<Joker>()
+            .map(PlayingCard::Joker)
+            .or_else(|_| s.parse::<Card>().map(PlayingCard::Card))
+    }
+}
+
+/// Parses a hand such as `"AH TH KH"`. Cards are separated by commas when
+/// there are any, so that long names like `"Ten of Spades, Ace of Hearts"`
+/// can be used, and by whitespace otherwise.
+impl<T> FromStr for Pile<T>
+where
+    T: FromStr,
+{
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Pile<T>, T::Err> {
+        let cards: Result<Vec<T>, T::Err> = if s.contains(',') {
+            s.split(',').map(str::parse).collect()
+        } else {
+            s.split_whitespace().map(str::parse).collect()
+        };
+
+        cards.map(Pile::from_vec)
+    }
+}
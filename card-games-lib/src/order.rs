@@ -0,0 +1,139 @@
+use crate::model::{Card, Rank, Suit};
+use core::cmp::Ordering;
+
+/// Ranks cards of the same suit against each other.
+pub trait RankOrder {
+    /// How strong a rank is; higher values beat lower ones.
+    fn strength(&self, rank: Rank) -> u8;
+
+    fn compare(&self, a: Rank, b: Rank) -> Ordering {
+        self.strength(a).cmp(&self.strength(b))
+    }
+}
+
+impl<O> RankOrder for &O
+where
+    O: RankOrder + ?Sized,
+{
+    fn strength(&self, rank: Rank) -> u8 {
+        (**self).strength(rank)
+    }
+}
+
+/// Two through king, then ace.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AceHigh;
+
+/// Ace, then two through king.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AceLow;
+
+/// Nine, jack, queen, king, ten, ace. Ranks below nine are weaker than all
+/// of them, in ace-low order.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct TysiacOrder;
+
+impl RankOrder for AceHigh {
+    fn strength(&self, rank: Rank) -> u8 {
+        match rank {
+            Rank::Ace => 14,
+            rank => AceLow.strength(rank),
+        }
+    }
+}
+
+impl RankOrder for AceLow {
+    fn strength(&self, rank: Rank) -> u8 {
+        match rank {
+            Rank::Ace => 1,
+            Rank::Two => 2,
+            Rank::Three => 3,
+            Rank::Four => 4,
+            Rank::Five => 5,
+            Rank::Six => 6,
+            Rank::Seven => 7,
+            Rank::Eight => 8,
+            Rank::Nine => 9,
+            Rank::Ten => 10,
+            Rank::Jack => 11,
+            Rank::Queen => 12,
+            Rank::King => 13,
+        }
+    }
+}
+
+impl RankOrder for TysiacOrder {
+    fn strength(&self, rank: Rank) -> u8 {
+        match rank {
+            Rank::Nine => 9,
+            Rank::Jack => 10,
+            Rank::Queen => 11,
+            Rank::King => 12,
+            Rank::Ten => 13,
+            Rank::Ace => 14,
+            rank => AceLow.strength(rank),
+        }
+    }
+}
+
+/// Compares cards played to a trick: trumps beat every other card, cards of
+/// the lead suit beat the remaining suits, and cards that neither follow
+/// nor trump can never win.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TrickOrder<O> {
+    pub order: O,
+    pub lead: Suit,
+    pub trump: Option<Suit>,
+}
+
+impl<O> TrickOrder<O>
+where
+    O: RankOrder,
+{
+    pub fn new(order: O, lead: Suit, trump: Option<Suit>) -> TrickOrder<O> {
+        TrickOrder { order, lead, trump }
+    }
+
+    fn key(&self, card: Card) -> (u8, u8) {
+        if Some(card.suit()) == self.trump {
+            (2, self.order.strength(card.rank()))
+        } else if card.suit() == self.lead {
+            (1, self.order.strength(card.rank()))
+        } else {
+            (0, 0)
+        }
+    }
+
+    pub fn compare(&self, a: Card, b: Card) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+
+    pub fn beats(&self, a: Card, b: Card) -> bool {
+        self.compare(a, b) == Ordering::Greater
+    }
+
+    /// The index of the winning card among `cards`, in the order they were
+    /// played.
+    pub fn winner(&self, cards: impl IntoIterator<Item = Card>) -> Option<usize> {
+        let mut best: Option<(usize, Card)> = None;
+
+        for (idx, card) in cards.into_iter().enumerate() {
+            match best {
+                Some((_, best_card)) if !self.beats(card, best_card) => {}
+                _ => best = Some((idx, card)),
+            }
+        }
+
+        best.map(|(idx, _)| idx)
+    }
+}
+
+/// The index of the card that wins a trick, taking the lead suit from the
+/// first card played.
+pub fn winning_card<O>(order: O, trump: Option<Suit>, cards: &[Card]) -> Option<usize>
+where
+    O: RankOrder,
+{
+    let lead = cards.first()?.suit();
+    TrickOrder::new(order, lead, trump).winner(cards.iter().copied())
+}
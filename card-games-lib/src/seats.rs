@@ -0,0 +1,171 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut};
+
+/// A position at the table. Seats are numbered from zero in the order play
+/// passes between them.
+pub trait Seat: Copy + Eq {
+    const COUNT: usize;
+
+    fn index(self) -> usize;
+
+    fn from_index(index: usize) -> Option<Self>;
+
+    /// The seat that plays after this one.
+    fn next(self) -> Self {
+        Self::from_index((self.index() + 1) % Self::COUNT).expect("Indices below COUNT are seats")
+    }
+
+    fn previous(self) -> Self {
+        Self::from_index((self.index() + Self::COUNT - 1) % Self::COUNT)
+            .expect("Indices below COUNT are seats")
+    }
+
+    /// The partnership the seat plays for. Seats with the same partnership
+    /// are partners; by default every seat plays for itself.
+    fn partnership(self) -> usize {
+        self.index()
+    }
+
+    fn is_partner(self, other: Self) -> bool {
+        self.partnership() == other.partnership()
+    }
+
+    /// Every seat once, in playing order, starting from this one.
+    fn round(self) -> SeatIter<Self> {
+        SeatIter {
+            next: self,
+            remaining: Some(Self::COUNT),
+        }
+    }
+
+    /// Every seat, in playing order, forever, starting from this one.
+    fn cycle(self) -> SeatIter<Self> {
+        SeatIter {
+            next: self,
+            remaining: None,
+        }
+    }
+
+    /// Every seat once, starting from the seat with index zero.
+    fn all() -> SeatIter<Self> {
+        Self::from_index(0)
+            .expect("There is at least one seat")
+            .round()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SeatIter<P> {
+    next: P,
+    remaining: Option<usize>,
+}
+
+impl<P> Iterator for SeatIter<P>
+where
+    P: Seat,
+{
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        match &mut self.remaining {
+            Some(0) => return None,
+            Some(remaining) => *remaining -= 1,
+            None => {}
+        }
+
+        let seat = self.next;
+        self.next = seat.next();
+        Some(seat)
+    }
+}
+
+/// One value for every seat at the table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Seats<P, T> {
+    values: Vec<T>,
+    seats: PhantomData<P>,
+}
+
+impl<P, T> Seats<P, T>
+where
+    P: Seat,
+{
+    pub fn new(f: impl FnMut(P) -> T) -> Seats<P, T> {
+        Seats {
+            values: P::all().map(f).collect(),
+            seats: PhantomData,
+        }
+    }
+
+    /// Takes the values in seat index order.
+    ///
+    /// Panics unless there is exactly one value per seat.
+    pub fn from_vec(values: Vec<T>) -> Seats<P, T> {
+        assert_eq!(values.len(), P::COUNT, "Need one value per seat");
+        Seats {
+            values,
+            seats: PhantomData,
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (P, &T)> {
+        P::all().zip(self.values.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (P, &mut T)> {
+        P::all().zip(self.values.iter_mut())
+    }
+
+    /// Every seat and its value, in playing order, starting from `seat`.
+    pub fn iter_from(&self, seat: P) -> impl Iterator<Item = (P, &T)> {
+        seat.round().map(move |seat| (seat, &self[seat]))
+    }
+
+    /// The seats partnered with `seat`, including itself.
+    pub fn partnership(&self, seat: P) -> impl Iterator<Item = (P, &T)> {
+        self.iter()
+            .filter(move |(other, _)| seat.is_partner(*other))
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(P, &T) -> U) -> Seats<P, U> {
+        Seats {
+            values: self.iter().map(|(seat, value)| f(seat, value)).collect(),
+            seats: PhantomData,
+        }
+    }
+}
+
+impl<P, T> Default for Seats<P, T>
+where
+    P: Seat,
+    T: Default,
+{
+    fn default() -> Seats<P, T> {
+        Seats::new(|_| T::default())
+    }
+}
+
+impl<P, T> Index<P> for Seats<P, T>
+where
+    P: Seat,
+{
+    type Output = T;
+
+    fn index(&self, seat: P) -> &T {
+        &self.values[seat.index()]
+    }
+}
+
+impl<P, T> IndexMut<P> for Seats<P, T>
+where
+    P: Seat,
+{
+    fn index_mut(&mut self, seat: P) -> &mut T {
+        &mut self.values[seat.index()]
+    }
+}
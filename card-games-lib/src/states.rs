@@ -1,3 +1,5 @@
+use crate::Graph;
+use core::convert::TryFrom;
 use either::Either;
 
 pub trait State {
@@ -11,6 +13,8 @@ pub trait Step {
     type Context;
     type Input;
     type ThisState;
+    /// A single state, or an enum of the states declared as a branch of
+    /// `game_states!`.
     type NextState: Into<Self::SomeState>;
     type Error;
 
@@ -21,6 +25,54 @@ pub trait Step {
     ) -> StepResult<Self::ThisState, Self::NextState, Self::Error>;
 }
 
+/// A named action a state accepts besides its `Step` input. `game_states!`
+/// generates a struct holding the arguments of each action in an `actions`
+/// module next to the states.
+pub trait Act<Action> {
+    type SomeState;
+    type Context;
+    type ThisState;
+    type NextState: Into<Self::SomeState>;
+    type Error;
+
+    fn act(
+        self,
+        game: &mut Self::Context,
+        action: Action,
+    ) -> StepResult<Self::ThisState, Self::NextState, Self::Error>;
+}
+
+/// A state, or a choice of states, that a step can move into. Implemented by
+/// `game_states!` to run the `on_enter` hook of the state entered.
+///
+/// The hook only runs when a step moves into the state. A state built
+/// directly, such as the first state of a game, has not been entered until
+/// `enter` is called on it.
+pub trait Enter {
+    type Context;
+    type Id;
+
+    fn id(&self) -> Self::Id;
+
+    fn enter(&mut self, context: &mut Self::Context);
+}
+
+/// Implemented by `game_states!` for its `State` enum: the graph of the
+/// states, and the `on_exit` and `on_transition` hooks run on each move.
+pub trait Machine: Copy {
+    type Context;
+
+    /// The states and transitions declared in `game_states!`.
+    fn graph() -> Graph;
+
+    /// Runs the `on_exit` hook of the state left.
+    fn on_exit(self, context: &mut Self::Context);
+
+    /// Runs on every move from one state to another, after the state left
+    /// has exited and before the next state is entered.
+    fn on_transition(context: &mut Self::Context, from: Self, to: Self);
+}
+
 #[derive(Debug)]
 pub struct StepResult<ThisState, NextState, Error>(
     pub Either<ThisState, NextState>,
@@ -45,18 +97,51 @@ impl<ThisState, NextState, Error> From<StepResult<ThisState, NextState, Error>>
 impl<ThisState, NextState, E, N> StepResult<ThisState, NextState, E>
 where
     ThisState: State<Id = N>,
-    NextState: State<Id = N>,
+    NextState: Enter<Id = N>,
 {
     pub fn this(self) -> Result<ThisState, Error<E, N>> {
         let Self(next, error) = self;
-        error.map_err(|e| Error::StepError(e)).and_then(|_| {
-            next.left().ok_or(Error::NotInCorrectStateError {
-                held: ThisState::state(),
-                given: NextState::state(),
+        error
+            .map_err(|e| Error::StepError(e))
+            .and_then(|_| match next {
+                Either::Left(this) => Ok(this),
+                Either::Right(next) => Err(Error::NotInCorrectStateError {
+                    held: ThisState::state(),
+                    given: next.id(),
+                }),
             })
-        })
     }
 
+    /// Like `next`, for a step that may move into one of several states
+    /// declared as a branch of `game_states!`: the state moved into must be
+    /// `S`.
+    pub fn next_to<S>(self) -> Result<S, Error<E, N>>
+    where
+        S: State<Id = N> + TryFrom<NextState, Error = NextState>,
+    {
+        let Self(next, error) = self;
+        error
+            .map_err(|e| Error::StepError(e))
+            .and_then(|_| match next {
+                Either::Left(_) => Err(Error::NotInCorrectStateError {
+                    held: S::state(),
+                    given: ThisState::state(),
+                }),
+                Either::Right(next) => {
+                    S::try_from(next).map_err(|next| Error::NotInCorrectStateError {
+                        held: S::state(),
+                        given: next.id(),
+                    })
+                }
+            })
+    }
+}
+
+impl<ThisState, NextState, E, N> StepResult<ThisState, NextState, E>
+where
+    ThisState: State<Id = N>,
+    NextState: State<Id = N>,
+{
     pub fn next(self) -> Result<NextState, Error<E, N>> {
         let Self(next, error) = self;
         error.map_err(|e| Error::StepError(e)).and_then(|_| {
@@ -66,7 +151,9 @@ where
             })
         })
     }
+}
 
+impl<ThisState, NextState, E> StepResult<ThisState, NextState, E> {
     pub fn stay(this: ThisState) -> Self {
         Self(Either::Left(this), Ok(()))
     }
@@ -82,6 +169,26 @@ where
     pub fn fail_continue(next: NextState, result: E) -> Self {
         Self(Either::Right(next), Err(result))
     }
+
+    /// If the step moved on to the next state, runs `on_exit` for the state
+    /// left, then `on_transition`, then the `on_enter` hook of the next
+    /// state.
+    #[doc(hidden)]
+    pub fn run_hooks<C, N>(self, context: &mut C, from: N) -> Self
+    where
+        NextState: Enter<Context = C, Id = N>,
+        N: Machine<Context = C>,
+    {
+        match self {
+            Self(Either::Right(mut next), result) => {
+                from.on_exit(context);
+                N::on_transition(context, from, next.id());
+                next.enter(context);
+                Self(Either::Right(next), result)
+            }
+            stay => stay,
+        }
+    }
 }
 
 #[macro_export]
@@ -99,15 +206,34 @@ macro_rules! step_try {
 #[macro_export]
 macro_rules! game_states {
     { context: $context:ty,
+      $( attributes: {
+        $( SomeState: $( #[$some_state_attr:meta] )+ $(,)? )?
+        $( StateInput: $( #[$input_attr:meta] )+ $(,)? )?
+        $( StateError: $( #[$error_attr:meta] )+ $(,)? )?
+      } $(,)? )?
+      $( on_transition: $on_transition:expr , )?
+      $( branches: {
+        $( $( #[$branch_attr:meta] )*
+           $branch:ident { $( $branch_state:ident ),+ $(,)? }
+        ),* $(,)?
+      } $(,)? )?
       states: {
-        $( $state:ident
+        $( $( #[$state_attr:meta] )*
+           $state:ident
          { $( $field:ident : $type:ty),* $(,)? }
          ( $( $arg:ident : $arg_type:ty),* $(,)? ) -> ( $next_state:ty , $error:ty $(,)? )
          $body:expr
+         $( ; action $action:ident
+              ( $( $action_arg:ident : $action_arg_type:ty),* $(,)? ) -> ( $action_next:ty , $action_error:ty $(,)? )
+              $action_body:expr
+         )*
+         $( ; on_enter: $on_enter:expr )?
+         $( ; on_exit: $on_exit:expr )?
         ),+ $(,)? }
      } => {
         $(
             #[derive(Debug)]
+            $( #[$state_attr] )*
             pub struct $state {
                 $( $field : $type ), *
             }
@@ -126,6 +252,7 @@ macro_rules! game_states {
                 }
             }
 
+            #[allow(unused_parens)]
             impl $crate::Step for $state {
                 type SomeState = SomeState;
                 type Context = $context;
@@ -136,49 +263,250 @@ macro_rules! game_states {
 
                 fn step(self, context: &mut $context, input: Self::Input) -> $crate::StepResult<Self::ThisState, Self::NextState, Self::Error>
                 {
+                    fn body<F>(body: F) -> F
+                    where
+                        F: FnOnce($state, &mut $context, $( $arg_type ), *) -> $crate::StepResult<$state, $next_state, $error>,
+                    {
+                        body
+                    }
+
                     let ($( $arg ), * ) = input;
-                    let func: &Fn(Self, &mut $context, $( $arg_type ), *) -> $crate::StepResult<Self::ThisState, Self::NextState, Self::Error> = &$body;
-                    func(self, context, $( $arg ), * )
+                    body($body)(self, context, $( $arg ), * ).run_hooks(context, self::State::$state)
+                }
+            }
+
+            $(
+                impl $crate::Act<self::actions::$action> for $state {
+                    type SomeState = SomeState;
+                    type Context = $context;
+                    type ThisState = self::$state;
+                    type NextState = $action_next;
+                    type Error = $action_error;
+
+                    fn act(self, context: &mut $context, action: self::actions::$action) -> $crate::StepResult<Self::ThisState, Self::NextState, Self::Error>
+                    {
+                        fn body<F>(body: F) -> F
+                        where
+                            F: FnOnce($state, &mut $context, $( $action_arg_type ),*) -> $crate::StepResult<$state, $action_next, $action_error>,
+                        {
+                            body
+                        }
+
+                        let self::actions::$action($( $action_arg ),*) = action;
+                        body($action_body)(self, context, $( $action_arg ),* ).run_hooks(context, self::State::$state)
+                    }
+                }
+            )*
+
+            impl $crate::Enter for $state {
+                type Context = $context;
+                type Id = self::State;
+
+                fn id(&self) -> self::State {
+                    self::State::$state
+                }
+
+                #[allow(unused_variables)]
+                fn enter(&mut self, context: &mut $context) {
+                    $(
+                        fn hook<F: FnOnce(&mut $state, &mut $context)>(hook: F) -> F {
+                            hook
+                        }
+
+                        hook($on_enter)(self, context);
+                    )?
                 }
             }
         )+
 
+        /// The arguments of each action, as given to `Act::act`.
+        pub mod actions {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $($(
+                #[derive(Debug, Clone, PartialEq, Eq)]
+                pub struct $action($( pub $action_arg_type ),*);
+            )*)+
+        }
+
         #[derive(Debug)]
+        $($($( #[$input_attr] )+)?)?
         pub enum StateInput {
-            $($state ( $( $arg_type),* ) ), +
+            $(
+                $state ( $( $arg_type),* ),
+                $( $action ( $( $action_arg_type ),* ), )*
+            )+
         }
 
         impl self::StateInput {
             pub fn state(&self) -> self::State {
                 match self {
-                    $(self::StateInput::$state($( $arg ),*) => self::State::$state ), +
+                    $(
+                        self::StateInput::$state(..) => self::State::$state,
+                        $( self::StateInput::$action(..) => self::State::$state, )*
+                    )+
                 }
             }
         }
 
         #[derive(Debug, PartialEq, Eq)]
+        $($($( #[$error_attr] )+)?)?
         pub enum StateError {
-            $( $state($error) ), +
+            $(
+                $state($error),
+                $( $action($action_error), )*
+            )+
         }
 
         impl self::StateError {
             pub fn state(&self) -> self::State {
                 match self {
-                    $(self::StateError::$state(_) => self::State::$state ), +
+                    $(
+                        self::StateError::$state(_) => self::State::$state,
+                        $( self::StateError::$action(_) => self::State::$state, )*
+                    )+
                 }
             }
         }
 
         #[derive(Debug)]
+        $($($( #[$some_state_attr] )+)?)?
         pub enum SomeState {
             $($state($state)), +
         }
 
-        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         pub enum State {
             $($state), +
         }
 
+        $($(
+            #[derive(Debug)]
+            $( #[$branch_attr] )*
+            pub enum $branch {
+                $( $branch_state($branch_state) ),+
+            }
+
+            $(
+                impl From<$branch_state> for $branch {
+                    fn from(state: $branch_state) -> $branch {
+                        $branch::$branch_state(state)
+                    }
+                }
+
+                impl ::core::convert::TryFrom<$branch> for $branch_state {
+                    type Error = $branch;
+
+                    #[allow(unreachable_patterns)]
+                    fn try_from(branch: $branch) -> Result<$branch_state, $branch> {
+                        match branch {
+                            $branch::$branch_state(state) => Ok(state),
+                            other => Err(other),
+                        }
+                    }
+                }
+            )+
+
+            impl From<$branch> for self::SomeState {
+                fn from(branch: $branch) -> self::SomeState {
+                    match branch {
+                        $( $branch::$branch_state(state) => self::SomeState::$branch_state(state) ),+
+                    }
+                }
+            }
+
+            impl $crate::Enter for $branch {
+                type Context = $context;
+                type Id = self::State;
+
+                fn id(&self) -> self::State {
+                    match self {
+                        $( $branch::$branch_state(state) => $crate::Enter::id(state) ),+
+                    }
+                }
+
+                fn enter(&mut self, context: &mut $context) {
+                    match self {
+                        $( $branch::$branch_state(state) => $crate::Enter::enter(state, context) ),+
+                    }
+                }
+            }
+        )*)?
+
+        impl $crate::Machine for self::State {
+            type Context = $context;
+
+            fn graph() -> $crate::Graph {
+                let mut graph = $crate::Graph::new(
+                    &[$( stringify!($state) ),+],
+                    &[$($( (stringify!($branch), &[$( stringify!($branch_state) ),+]) ),*)?],
+                );
+                $(
+                    graph.transition(
+                        stringify!($state),
+                        stringify!($state),
+                        &[$( stringify!($arg_type) ),*],
+                        stringify!($next_state),
+                        stringify!($error),
+                    );
+                    $(
+                        graph.transition(
+                            stringify!($state),
+                            stringify!($action),
+                            &[$( stringify!($action_arg_type) ),*],
+                            stringify!($action_next),
+                            stringify!($action_error),
+                        );
+                    )*
+                )+
+                graph
+            }
+
+            #[allow(unused_variables)]
+            fn on_exit(self, context: &mut $context) {
+                match self {
+                    $(
+                        self::State::$state => {
+                            $(
+                                fn hook<F: FnOnce(&mut $context)>(hook: F) -> F {
+                                    hook
+                                }
+
+                                hook($on_exit)(context);
+                            )?
+                        }
+                    )+
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn on_transition(context: &mut $context, from: self::State, to: self::State) {
+                $(
+                    fn hook<F: FnOnce(&mut $context, State, State)>(hook: F) -> F {
+                        hook
+                    }
+
+                    hook($on_transition)(context, from, to);
+                )?
+            }
+        }
+
+        impl $crate::Enter for SomeState {
+            type Context = $context;
+            type Id = self::State;
+
+            fn id(&self) -> self::State {
+                self.state()
+            }
+
+            fn enter(&mut self, context: &mut $context) {
+                match self {
+                    $(self::SomeState::$state(state) => $crate::Enter::enter(state, context) ), +
+                }
+            }
+        }
+
         impl SomeState {
             pub fn state(&self) -> self::State {
                 match self {
@@ -190,10 +518,18 @@ macro_rules! game_states {
             {
                 match (self, input) {
                     $((self::SomeState::$state(state), self::StateInput::$state( $( $arg ), * )) => {
-                        let self::StepResult(next, result) = state.step(context, ($( $arg ), *));
+                        let $crate::StepResult(next, result) = $crate::Step::step(state, context, ($( $arg ), *));
                         let err = result.map_err(|e| $crate::Error::StepError(self::StateError::$state(e)));
                         (::core::convert::Into::into(next), err)
-                    }),+
+                    }
+                    $(
+                        (self::SomeState::$state(state), self::StateInput::$action( $( $action_arg ),* )) => {
+                            let $crate::StepResult(next, result) = $crate::Act::act(state, context, self::actions::$action($( $action_arg ),*));
+                            let err = result.map_err(|e| $crate::Error::StepError(self::StateError::$action(e)));
+                            (::core::convert::Into::into(next), err)
+                        }
+                    )*
+                    ),+
                     $((SomeState::$state(a), input) =>{
                         (self::SomeState::$state(a), Err($crate::Error::NotInCorrectStateError{
                             held: self::State::$state,
@@ -204,14 +540,14 @@ macro_rules! game_states {
             }
         }
 
-        impl<A, B> From<::either::Either<A, B>> for SomeState
+        impl<A, B> From<$crate::__private::Either<A, B>> for SomeState
         where A : Into<SomeState>,
             B : Into<SomeState>
         {
-            fn from(s: ::either::Either<A, B>) -> SomeState {
+            fn from(s: $crate::__private::Either<A, B>) -> SomeState {
                 match s {
-                    ::either::Either::Left(a) => a.into(),
-                    ::either::Either::Right(b) => b.into()
+                    $crate::__private::Either::Left(a) => a.into(),
+                    $crate::__private::Either::Right(b) => b.into()
                 }
             }
         }
@@ -0,0 +1,553 @@
+use super::*;
+use std::cmp::Ordering;
+use {Rank::*, Suit::*};
+
+#[test]
+fn deck_sizes() {
+    assert_eq!(Deck::<Card>::standard().len(), 52);
+    assert_eq!(Deck::<Card>::piquet().len(), 32);
+    assert_eq!(Deck::<Card>::schnapsen().len(), 24);
+    assert_eq!(Deck::<PlayingCard>::with_jokers().len(), 54);
+}
+
+#[test]
+fn jokers_in_deck() {
+    let deck = Deck::<PlayingCard>::with_jokers();
+    assert_eq!(deck.iter().filter(|c| c.is_joker()).count(), 2);
+    assert!(deck.iter().any(|c| Joker::Red == *c));
+    assert!(deck.iter().any(|c| Joker::Black == *c));
+}
+
+#[test]
+fn wild_descriptions() {
+    let mut hand: Pile<PlayingCard> = pile![
+        Card(King, Hearts).into(),
+        Joker::Red.into(),
+        Card(Two, Spades).into(),
+    ];
+
+    assert!(hand.contains(&Card(King, Hearts)));
+    assert!(!hand.contains(&Card(King, Spades)));
+    assert!(hand.contains(&Wild::Suit(Spades)));
+    assert!(!hand.contains(&Wild::Rank(Ace)));
+
+    let kings = hand.extract(
+        vec![
+            Wild::OrJoker(Card(King, Hearts)),
+            Wild::OrJoker(Card(King, Hearts)),
+        ]
+        .into_iter(),
+    );
+    assert_eq!(kings.map(|k| k.len()), Some(2));
+    assert_eq!(hand.remove(&Wild::Any), Some(Card(Two, Spades).into()));
+    assert_eq!(hand.remove(&Wild::Joker), None);
+}
+
+#[test]
+fn seeded_shuffle_is_repeatable() {
+    let mut a = Deck::<Card>::standard();
+    let mut b = Deck::<Card>::standard();
+    a.shuffle_seeded(7);
+    b.shuffle_seeded(7);
+    assert_eq!(a, b);
+    assert_ne!(a, Deck::standard());
+}
+
+#[test]
+fn cut_moves_top_to_bottom() {
+    let mut deck = Deck::from_vec(vec![1, 2, 3, 4, 5]);
+    deck.cut(2);
+    assert_eq!(deck, Deck::from_vec(vec![3, 4, 5, 1, 2]));
+}
+
+#[test]
+fn deal_packets_2_3_2() {
+    let mut deck = Deck::from_vec((0..22).collect());
+    let mut hands = [pile!(), pile!(), pile!()];
+    deck.deal_packets(&mut hands, &[2, 3, 2]);
+
+    assert_eq!(hands[0], Pile::from_vec(vec![0, 1, 6, 7, 8, 15, 16]));
+    assert_eq!(hands[1], Pile::from_vec(vec![2, 3, 9, 10, 11, 17, 18]));
+    assert_eq!(hands[2], Pile::from_vec(vec![4, 5, 12, 13, 14, 19, 20]));
+    assert_eq!(deck.draw(), Some(21));
+    assert_eq!(deck.draw(), None);
+}
+
+#[test]
+fn deal_round_robin() {
+    let mut deck = Deck::from_vec((0..5).collect());
+    let mut hands = [pile!(), pile!()];
+    deck.deal_round_robin(&mut hands, 3);
+
+    assert_eq!(hands[0], Pile::from_vec(vec![0, 2, 4]));
+    assert_eq!(hands[1], Pile::from_vec(vec![1, 3]));
+}
+
+#[test]
+fn rank_orders() {
+    assert_eq!(AceHigh.compare(Ace, King), Ordering::Greater);
+    assert_eq!(AceLow.compare(Ace, Two), Ordering::Less);
+    assert_eq!(TysiacOrder.compare(Ten, King), Ordering::Greater);
+    assert_eq!(TysiacOrder.compare(Ten, Ace), Ordering::Less);
+    assert_eq!(TysiacOrder.compare(Nine, Jack), Ordering::Less);
+}
+
+#[test]
+fn trick_winner() {
+    let trick = [Card(Nine, Hearts), Card(Ace, Spades), Card(Ten, Hearts)];
+
+    assert_eq!(winning_card(TysiacOrder, None, &trick), Some(2));
+    assert_eq!(winning_card(TysiacOrder, Some(Spades), &trick), Some(1));
+    assert_eq!(winning_card(TysiacOrder, Some(Clubs), &trick), Some(2));
+    assert_eq!(winning_card(AceHigh, None, &[]), None);
+
+    let order = TrickOrder::new(AceHigh, Diamonds, Some(Clubs));
+    assert!(order.beats(Card(Two, Clubs), Card(Ace, Diamonds)));
+    assert!(!order.beats(Card(Ace, Hearts), Card(Two, Diamonds)));
+    assert_eq!(
+        order.winner(vec![Card(Ace, Hearts), Card(King, Spades)]),
+        Some(0)
+    );
+}
+
+#[test]
+fn trick_follow_and_winner() {
+    let rules = TrickRules {
+        order: AceHigh,
+        must_follow: true,
+        must_trump: true,
+        must_overtake: true,
+    };
+    let mut trick = Trick::new(0);
+
+    trick.play(0, Card(Ten, Hearts));
+    let hand = vec![Card(Two, Hearts), Card(Ace, Hearts), Card(Three, Spades)];
+    assert_eq!(
+        rules.check(&trick, Some(Spades), hand.clone(), Card(Three, Spades)),
+        Err(TrickError::MustFollow(Hearts))
+    );
+    assert_eq!(
+        rules.check(&trick, Some(Spades), hand.clone(), Card(Two, Hearts)),
+        Err(TrickError::MustOvertake)
+    );
+    assert_eq!(
+        rules.check(&trick, Some(Spades), hand, Card(Ace, Hearts)),
+        Ok(())
+    );
+    trick.play(1, Card(Ace, Hearts));
+
+    let hand = vec![Card(Two, Spades), Card(Four, Clubs)];
+    assert_eq!(
+        rules.check(&trick, Some(Spades), hand.clone(), Card(Four, Clubs)),
+        Err(TrickError::MustTrump(Spades))
+    );
+    assert_eq!(
+        rules.check(&trick, Some(Spades), hand, Card(Two, Spades)),
+        Ok(())
+    );
+    trick.play(2, Card(Two, Spades));
+
+    assert_eq!(trick.winner(AceHigh, None), Some(1));
+    assert_eq!(trick.winner(AceHigh, Some(Spades)), Some(2));
+
+    let mut taken = TakenTricks::default();
+    taken.record(2, trick);
+    assert_eq!(taken.count(2), 1);
+    assert_eq!(taken.count(0), 0);
+    assert_eq!(taken.cards_won_by(2).count(), 3);
+}
+
+#[test]
+fn notation_round_trips() {
+    for notation in &[Notation::Ascii, Notation::Unicode, Notation::Long] {
+        for card in Deck::<PlayingCard>::with_jokers() {
+            let written = notation.show(card).to_string();
+            assert_eq!(written.parse(), Ok(card), "{}", written);
+        }
+    }
+
+    assert_eq!(Card(Ten, Spades).to_string(), "TS");
+    assert_eq!(Notation::Unicode.show(Card(Ten, Spades)).to_string(), "10♠");
+    assert_eq!(
+        Notation::Long.show(Card(Ten, Spades)).to_string(),
+        "Ten of Spades"
+    );
+}
+
+#[test]
+fn parse_cards() {
+    assert_eq!("9c".parse(), Ok(Card(Nine, Clubs)));
+    assert_eq!("10♠".parse(), Ok(Card(Ten, Spades)));
+    assert_eq!("queen of hearts".parse(), Ok(Card(Queen, Hearts)));
+    assert_eq!("king of diamond".parse(), Ok(Card(King, Diamonds)));
+    assert_eq!(
+        "XH".parse::<Card>(),
+        Err(ParseError::UnknownRank("X".to_owned()))
+    );
+    assert_eq!(
+        "AX".parse::<Card>(),
+        Err(ParseError::UnknownSuit("X".to_owned()))
+    );
+    assert_eq!("".parse::<Card>(), Err(ParseError::Empty));
+}
+
+#[test]
+fn parse_hands() {
+    let hand: Pile<Card> = "AH TH KH".parse().unwrap();
+    assert_eq!(
+        hand,
+        pile![Card(Ace, Hearts), Card(Ten, Hearts), Card(King, Hearts)]
+    );
+    assert_eq!(hand.to_string(), "AH TH KH");
+
+    let hand: Pile<Card> = "Ten of Spades, Ace of Hearts".parse().unwrap();
+    assert_eq!(hand, pile![Card(Ten, Spades), Card(Ace, Hearts)]);
+
+    let hand: Pile<PlayingCard> = "RJ 2C".parse().unwrap();
+    assert_eq!(
+        hand,
+        pile![Joker::Red.into(), PlayingCard::Card(Card(Two, Clubs))]
+    );
+}
+
+#[test]
+fn sort_and_group() {
+    let mut hand: Pile<Card> = "9H AS TH 2C KS AH".parse().unwrap();
+
+    hand.sort(TysiacOrder);
+    assert_eq!(hand.to_string(), "2C AH TH 9H AS KS");
+    hand.sort(AceLow);
+    assert_eq!(hand.to_string(), "2C TH 9H AH KS AS");
+
+    let groups = hand.group_by_suit();
+    assert_eq!(
+        groups.keys().copied().collect::<Vec<_>>(),
+        [Clubs, Hearts, Spades]
+    );
+    assert_eq!(groups[&Hearts].len(), 3);
+    assert_eq!(hand.count_suit(Spades), 2);
+    assert_eq!(hand.count_suit(Diamonds), 0);
+    assert_eq!(hand.highest_of(Hearts, AceHigh), Some(&Card(Ace, Hearts)));
+    assert_eq!(hand.lowest_of(Hearts, AceHigh), Some(&Card(Nine, Hearts)));
+    assert_eq!(hand.highest_of(Diamonds, AceHigh), None);
+
+    let hearts = hand.split_off(&Wild::Suit(Hearts));
+    assert_eq!(hearts.to_string(), "TH 9H AH");
+    assert_eq!(hand.to_string(), "2C KS AS");
+}
+
+#[test]
+fn stock_and_discard() {
+    let mut stock: Pile<Card> = "2C 3C 4C 5C".parse().unwrap();
+    let mut discard = pile!();
+
+    assert_eq!(stock.peek(), Some(&Card(Two, Clubs)));
+    for card in stock.take_top(2).drain() {
+        discard.put_on_top(card);
+    }
+    assert_eq!(discard.peek(), Some(&Card(Three, Clubs)));
+    assert_eq!(discard.to_string(), "3C 2C");
+
+    let top = stock.take_top(5);
+    assert!(stock.is_empty());
+    assert_eq!(top.to_string(), "4C 5C");
+
+    let mut pile = top;
+    let bottom = pile.split_at(1);
+    assert_eq!(pile.to_string(), "4C");
+    assert_eq!(bottom.to_string(), "5C");
+
+    pile.insert_at(0, Card(Ace, Spades));
+    assert_eq!(pile.to_string(), "AS 4C");
+
+    let mut rng = rand::rngs::mock::StepRng::new(0, 1);
+    pile.shuffle(&mut rng);
+    assert_eq!(pile.len(), 2);
+}
+
+#[test]
+fn decks_and_piles_share_a_top() {
+    let mut deck = Deck::from_vec(vec![1, 2, 3, 4]);
+    let mut stock = deck.deal(3);
+
+    assert_eq!(deck.draw(), Some(4));
+    assert_eq!(stock.peek(), Some(&1));
+    assert_eq!(stock.take_top(2), Pile::from_vec(vec![1, 2]));
+}
+
+#[test]
+fn try_extract_leaves_specific_cards_for_later_descriptions() {
+    let mut hand: Pile<Card> = "KH KS AH 9S".parse().unwrap();
+
+    assert_eq!(
+        hand.try_extract(vec![Wild::Rank(King), Wild::OrJoker(Card(King, Hearts))]),
+        Ok(vec![Card(King, Spades), Card(King, Hearts)])
+    );
+    assert_eq!(
+        hand.try_extract(vec![Wild::Any, Wild::Any, Wild::Suit(Hearts)]),
+        Err(ExtractError::Duplicate {
+            index: 2,
+            description: Wild::Suit(Hearts)
+        })
+    );
+    assert_eq!(
+        hand.try_extract(vec![Wild::Any, Wild::OrJoker(Card(Ace, Hearts))]),
+        Ok(vec![Card(Nine, Spades), Card(Ace, Hearts)])
+    );
+}
+
+#[test]
+fn try_extract_reports_failures() {
+    let mut hand: Pile<Card> = "AH KH KS".parse().unwrap();
+
+    assert_eq!(
+        hand.try_extract(vec![Card(King, Hearts), Card(Queen, Hearts)]),
+        Err(ExtractError::Missing {
+            index: 1,
+            description: Card(Queen, Hearts)
+        })
+    );
+    assert_eq!(
+        hand.try_extract(vec![Card(King, Hearts), Card(King, Hearts)]),
+        Err(ExtractError::Duplicate {
+            index: 1,
+            description: Card(King, Hearts)
+        })
+    );
+    assert_eq!(hand.len(), 3);
+
+    assert_eq!(
+        pile_extract!(try &mut hand, Card(King, Spades), Card(Ace, Hearts)),
+        Ok((Card(King, Spades), Card(Ace, Hearts)))
+    );
+    assert_eq!(hand.to_string(), "KH");
+    assert_eq!(
+        pile_extract!(&mut hand, Card(Ace, Hearts), Card(King, Hearts)),
+        None
+    );
+}
+
+#[test]
+fn card_set_algebra() {
+    let hand: CardSet = "AH TH KS 2C"
+        .parse::<Pile<Card>>()
+        .unwrap()
+        .iter()
+        .copied()
+        .collect();
+    let hearts = CardSet::of_suit(Hearts);
+
+    assert_eq!(hand.len(), 4);
+    assert!(hand.contains(Card(Ten, Hearts)));
+    assert!(!hand.contains(Card(Ten, Spades)));
+    assert_eq!((hand & hearts).len(), 2);
+    assert_eq!(hand.suit(Hearts), hand & hearts);
+    assert_eq!((hand - hearts).to_pile().to_string(), "2C KS");
+    assert_eq!((hand | hearts).len(), 15);
+    assert_eq!((!hand).len(), 48);
+    assert!(hand.suit(Spades).is_subset(hand));
+    assert_eq!(CardSet::of_rank(Ace).len(), 4);
+    assert_eq!(CardSet::FULL.complement(), CardSet::EMPTY);
+
+    let mut set = CardSet::EMPTY;
+    assert!(set.insert(Card(King, Diamonds)));
+    assert!(!set.insert(Card(King, Diamonds)));
+    assert!(set.remove(Card(King, Diamonds)));
+    assert!(set.is_empty());
+}
+
+#[test]
+fn card_set_iterates_in_rank_order() {
+    let deck: CardSet = Deck::<Card>::standard().into_iter().collect();
+    assert_eq!(deck, CardSet::FULL);
+
+    let cards: Vec<Card> = deck.iter().take(5).collect();
+    assert_eq!(
+        cards,
+        [
+            Card(Ace, Diamonds),
+            Card(Ace, Clubs),
+            Card(Ace, Hearts),
+            Card(Ace, Spades),
+            Card(Two, Diamonds)
+        ]
+    );
+
+    let pile: Pile<Card> = "KS 9H 9C".parse().unwrap();
+    assert_eq!(CardSet::from(&pile).to_pile().to_string(), "9C 9H KS");
+}
+
+#[test]
+fn legal_plays_on_card_set() {
+    let rules = TrickRules {
+        order: AceHigh,
+        must_follow: true,
+        must_trump: true,
+        must_overtake: false,
+    };
+    let mut trick = Trick::new(0);
+    let hand: CardSet = [Card(Two, Hearts), Card(Ace, Hearts), Card(Three, Spades)]
+        .iter()
+        .copied()
+        .collect();
+
+    assert_eq!(rules.legal_plays(&trick, Some(Spades), hand), hand);
+    trick.play(0, Card(Ten, Hearts));
+    assert_eq!(
+        rules.legal_plays(&trick, Some(Spades), hand),
+        hand.suit(Hearts)
+    );
+    assert_eq!(
+        rules.legal_plays(
+            &trick,
+            Some(Spades),
+            hand.suit(Spades) | CardSet::of_suit(Clubs)
+        ),
+        hand.suit(Spades)
+    );
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Compass {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Seat for Compass {
+    const COUNT: usize = 4;
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn from_index(index: usize) -> Option<Compass> {
+        [Compass::North, Compass::East, Compass::South, Compass::West]
+            .get(index)
+            .copied()
+    }
+
+    fn partnership(self) -> usize {
+        self.index() % 2
+    }
+}
+
+#[test]
+fn seats_and_partnerships() {
+    use Compass::*;
+
+    assert_eq!(West.next(), North);
+    assert_eq!(North.previous(), West);
+    assert_eq!(
+        South.cycle().take(5).collect::<Vec<_>>(),
+        [South, West, North, East, South]
+    );
+    assert!(North.is_partner(South));
+    assert!(!North.is_partner(East));
+
+    let mut tricks: Seats<Compass, usize> = Seats::default();
+    tricks[North] += 3;
+    tricks[South] += 4;
+    tricks[East] += 6;
+
+    let north_south: usize = tricks.partnership(North).map(|(_, n)| n).sum();
+    assert_eq!(north_south, 7);
+    assert_eq!(
+        tricks
+            .iter_from(East)
+            .map(|(seat, _)| seat)
+            .collect::<Vec<_>>(),
+        [East, South, West, North]
+    );
+    assert_eq!(tricks.map(|_, n| n * 2).into_vec(), [6, 12, 8, 0]);
+}
+
+mod counting {
+    use crate::{game_states, StepResult};
+
+    #[derive(Debug, Default)]
+    pub struct Log(pub Vec<String>);
+
+    game_states! {
+        context: Log,
+        on_transition: |log, from, to| log.0.push(format!("{:?} -> {:?}", from, to)),
+        states: {
+            Counting {
+                count: u8,
+            } (by: u8) -> (Done, String) |this, _log, by| {
+                match this.count.checked_add(by) {
+                    Some(count) if count < 10 => StepResult::stay(Counting { count }),
+                    Some(count) => StepResult::cont(Done { count }),
+                    None => StepResult::fail(this, "Count overflowed".to_owned()),
+                }
+            };
+            on_exit: |log| log.0.push("exit Counting".to_owned()),
+            Done {
+                count: u8,
+            } () -> (Done, String) |this, _log| StepResult::stay(this);
+            on_enter: |this, log| log.0.push(format!("enter Done at {}", this.count))
+        }
+    }
+
+    pub fn start() -> SomeState {
+        SomeState::Counting(Counting { count: 0 })
+    }
+}
+
+#[test]
+fn state_hooks_run_on_transitions() {
+    use counting::*;
+
+    let mut log = Log::default();
+    let (state, result) = start().step(&mut log, StateInput::Counting(4));
+    assert_eq!(result, Ok(()));
+
+    let (state, result) = state.step(&mut log, StateInput::Counting(255));
+    match result {
+        Err(crate::Error::StepError(error)) => assert_eq!(error.state(), State::Counting),
+        result => panic!("Expected the count to overflow, got {:?}", result),
+    }
+    assert!(log.0.is_empty());
+
+    let (state, result) = state.step(&mut log, StateInput::Counting(7));
+    assert_eq!(result, Ok(()));
+    assert_eq!(state.state(), State::Done);
+    assert_eq!(
+        log.0,
+        ["exit Counting", "Counting -> Done", "enter Done at 11"]
+    );
+
+    let (_, result) = state.step(&mut log, StateInput::Done());
+    assert_eq!(result, Ok(()));
+    assert_eq!(log.0.len(), 3);
+}
+
+#[test]
+fn state_graph_renders() {
+    use counting::State;
+
+    let graph = State::graph();
+    assert_eq!(graph.states, ["Counting", "Done"]);
+    assert_eq!(graph.transitions[0].args, ["u8"]);
+    assert!(graph.unreachable().is_empty());
+    assert_eq!(
+        graph.to_dot(),
+        "digraph {\n    \"Counting\";\n    \"Done\";\n    \"Counting\" -> \"Done\" [label=\"Counting(u8) -> Err(String)\"];\n    \"Done\" -> \"Done\" [label=\"Done() -> Err(String)\"];\n}\n"
+    );
+    assert_eq!(
+        graph.to_mermaid(),
+        "stateDiagram-v2\n    [*] --> Counting\n    Counting --> Done: Counting(u8) -#gt; Err(String)\n    Done --> Done: Done() -#gt; Err(String)\n"
+    );
+
+    let mut graph = Graph::new(
+        &["Start", "Middle", "Lost"],
+        &[("Fork", &["Middle", "Start"])],
+    );
+    graph.transition("Start", "Go", &["Option<u8>"], "Fork", "String");
+    assert_eq!(graph.transitions[0].to, ["Middle", "Start"]);
+    assert_eq!(graph.unreachable(), ["Lost"]);
+    assert!(graph
+        .to_mermaid()
+        .contains("Start --> Middle: Go(Option#lt;u8#gt;) -#gt; Err(String)"));
+}
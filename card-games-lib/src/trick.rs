@@ -0,0 +1,225 @@
+use crate::cardset::CardSet;
+use crate::model::{Card, Describe, Suit};
+use crate::order::{RankOrder, TrickOrder};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The cards played to a single trick, along with the seat that played each.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Trick<S, T> {
+    leader: S,
+    plays: Vec<(S, T)>,
+}
+
+impl<S, T> Trick<S, T>
+where
+    S: Copy + Eq,
+{
+    pub fn new(leader: S) -> Trick<S, T> {
+        Trick {
+            leader,
+            plays: vec![],
+        }
+    }
+
+    pub fn leader(&self) -> S {
+        self.leader
+    }
+
+    pub fn len(&self) -> usize {
+        self.plays.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plays.is_empty()
+    }
+
+    pub fn play(&mut self, seat: S, card: T) {
+        self.plays.push((seat, card))
+    }
+
+    pub fn lead(&self) -> Option<&T> {
+        self.plays.first().map(|(_, card)| card)
+    }
+
+    pub fn plays(&self) -> impl Iterator<Item = &(S, T)> {
+        self.plays.iter()
+    }
+
+    pub fn cards(&self) -> impl Iterator<Item = &T> {
+        self.plays.iter().map(|(_, card)| card)
+    }
+
+    pub fn into_cards(self) -> impl Iterator<Item = T> {
+        self.plays.into_iter().map(|(_, card)| card)
+    }
+}
+
+impl<S, T> Trick<S, T>
+where
+    S: Copy + Eq,
+    T: Describe,
+{
+    pub fn lead_suit(&self) -> Option<Suit> {
+        self.lead().map(|card| card.description().suit())
+    }
+
+    /// The play currently winning the trick.
+    pub fn winning<O>(&self, order: O, trump: Option<Suit>) -> Option<&(S, T)>
+    where
+        O: RankOrder,
+    {
+        let lead = self.lead_suit()?;
+        let idx =
+            TrickOrder::new(order, lead, trump).winner(self.cards().map(Describe::description))?;
+        self.plays.get(idx)
+    }
+
+    pub fn winner<O>(&self, order: O, trump: Option<Suit>) -> Option<S>
+    where
+        O: RankOrder,
+    {
+        self.winning(order, trump).map(|(seat, _)| *seat)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TrickError {
+    MustFollow(Suit),
+    MustTrump(Suit),
+    MustOvertake,
+}
+
+/// The obligations a player is under when adding a card to a trick.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TrickRules<O> {
+    pub order: O,
+    /// Play a card of the lead suit if able.
+    pub must_follow: bool,
+    /// Play a trump if unable to follow.
+    pub must_trump: bool,
+    /// Beat the winning card if able, when following or trumping.
+    pub must_overtake: bool,
+}
+
+impl<O> TrickRules<O>
+where
+    O: RankOrder + Copy,
+{
+    /// Checks that `card` may be added to `trick` by a player holding `hand`.
+    /// The card itself is expected to be part of the hand.
+    pub fn check<S, T>(
+        &self,
+        trick: &Trick<S, T>,
+        trump: Option<Suit>,
+        hand: impl IntoIterator<Item = Card>,
+        card: Card,
+    ) -> Result<(), TrickError>
+    where
+        S: Copy + Eq,
+        T: Describe,
+    {
+        let lead = match trick.lead_suit() {
+            Some(lead) => lead,
+            None => return Ok(()),
+        };
+        let hand: Vec<Card> = hand.into_iter().collect();
+        let holds = |suit: Suit| hand.iter().any(|c| c.suit() == suit);
+
+        if card.suit() != lead {
+            if self.must_follow && holds(lead) {
+                return Err(TrickError::MustFollow(lead));
+            }
+
+            if let Some(trump) = trump {
+                if self.must_trump && card.suit() != trump && !holds(lead) && holds(trump) {
+                    return Err(TrickError::MustTrump(trump));
+                }
+            }
+        }
+
+        if self.must_overtake && (card.suit() == lead || Some(card.suit()) == trump) {
+            let order = TrickOrder::new(self.order, lead, trump);
+            let best = trick
+                .winning(self.order, trump)
+                .map(|(_, c)| c.description())
+                .expect("A trick with a lead has a winner");
+
+            let could_overtake = hand
+                .iter()
+                .any(|c| c.suit() == card.suit() && order.beats(*c, best));
+
+            if !order.beats(card, best) && could_overtake {
+                return Err(TrickError::MustOvertake);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The cards of `hand` that `check` accepts.
+    pub fn legal_plays<S, T>(
+        &self,
+        trick: &Trick<S, T>,
+        trump: Option<Suit>,
+        hand: CardSet,
+    ) -> CardSet
+    where
+        S: Copy + Eq,
+        T: Describe,
+    {
+        hand.iter()
+            .filter(|card| self.check(trick, trump, hand, *card).is_ok())
+            .collect()
+    }
+}
+
+/// The completed tricks of a hand, in the order they were taken.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TakenTricks<S, T>(Vec<(S, Trick<S, T>)>);
+
+impl<S, T> Default for TakenTricks<S, T> {
+    fn default() -> TakenTricks<S, T> {
+        TakenTricks(vec![])
+    }
+}
+
+impl<S, T> TakenTricks<S, T>
+where
+    S: Copy + Eq,
+{
+    pub fn record(&mut self, winner: S, trick: Trick<S, T>) {
+        self.0.push((winner, trick))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(S, Trick<S, T>)> {
+        self.0.iter()
+    }
+
+    pub fn last(&self) -> Option<&(S, Trick<S, T>)> {
+        self.0.last()
+    }
+
+    pub fn won_by(&self, seat: S) -> impl Iterator<Item = &Trick<S, T>> {
+        self.0
+            .iter()
+            .filter(move |(winner, _)| *winner == seat)
+            .map(|(_, trick)| trick)
+    }
+
+    pub fn count(&self, seat: S) -> usize {
+        self.won_by(seat).count()
+    }
+
+    pub fn cards_won_by(&self, seat: S) -> impl Iterator<Item = &T> {
+        self.won_by(seat).flat_map(|trick| trick.cards())
+    }
+}
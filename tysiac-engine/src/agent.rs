@@ -0,0 +1,162 @@
+use card_games_lib::{CardSet, Pile, Seats, TakenTricks, Trick};
+use rand::{seq::SliceRandom, Rng};
+use tysiac::{Card, Fives, Game, Player, Playing, SomeState, State, StateInput, Suit};
+
+/// The game as seen from one seat: the seat's own hand and whatever is on
+/// the table, but never the other hands.
+pub struct View<'a> {
+    seat: Player,
+    state: &'a SomeState,
+    game: &'a Game,
+}
+
+/// A player that chooses the input for its seat whenever it is to move.
+pub trait Agent {
+    fn act(&mut self, view: &View<'_>) -> StateInput;
+}
+
+impl<A> Agent for Box<A>
+where
+    A: Agent + ?Sized,
+{
+    fn act(&mut self, view: &View<'_>) -> StateInput {
+        (**self).act(view)
+    }
+}
+
+impl<'a> View<'a> {
+    pub fn new(seat: Player, state: &'a SomeState, game: &'a Game) -> View<'a> {
+        View { seat, state, game }
+    }
+
+    pub fn seat(&self) -> Player {
+        self.seat
+    }
+
+    pub fn state(&self) -> State {
+        self.state.state()
+    }
+
+    pub fn to_move(&self) -> Player {
+        self.state.next_player()
+    }
+
+    pub fn hand(&self) -> Option<&'a Pile<Card>> {
+        self.state.hand(&self.seat)
+    }
+
+    /// How many cards `player` holds. The cards themselves are hidden.
+    pub fn hand_len(&self, player: Player) -> usize {
+        self.state.hand(&player).map_or(0, Pile::len)
+    }
+
+    pub fn points(&self, player: Player) -> isize {
+        self.game.points(player)
+    }
+
+    /// See `SomeState::bid`.
+    pub fn bid(&self) -> Option<(Player, usize)> {
+        self.state.bid()
+    }
+
+    pub fn trick(&self) -> Option<&'a Trick<Player, Card>> {
+        self.state.trick()
+    }
+
+    pub fn trump(&self) -> Option<&'a Suit> {
+        self.state.trump()
+    }
+
+    /// The tricks taken so far this hand, which every seat has seen played.
+    pub fn taken(&self) -> Option<&'a TakenTricks<Player, Card>> {
+        self.state.taken()
+    }
+
+    /// The prikup, which every seat saw the bid winner pick up.
+    pub fn prikup(&self) -> Option<CardSet> {
+        self.state.prikup()
+    }
+
+    /// The card the seat passed to each player, if it won the bid.
+    pub fn passed(&self) -> Option<&'a Seats<Player, CardSet>> {
+        match self.bid() {
+            Some((bid_winner, _)) if bid_winner == self.seat => self.state.passed(),
+            _ => None,
+        }
+    }
+
+    /// The full state for searches, which must replace the hidden hands
+    /// before looking at them.
+    pub(crate) fn playing(&self) -> Option<&'a Playing> {
+        match self.state {
+            SomeState::Playing(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn game(&self) -> &'a Game {
+        self.game
+    }
+
+    /// The cards the seat may play, if it is the seat's turn to play one.
+    pub fn legal_cards(&self) -> CardSet {
+        match self.state {
+            SomeState::Playing(x) if self.to_move() == self.seat => x.legal_cards(),
+            _ => CardSet::EMPTY,
+        }
+    }
+}
+
+/// Picks uniformly among legal inputs. It raises the bid by ten a third of
+/// the time while the bid is below 200, and never adjusts a won bid.
+pub struct RandomAgent<R> {
+    rng: R,
+}
+
+impl<R> RandomAgent<R>
+where
+    R: Rng,
+{
+    pub fn new(rng: R) -> RandomAgent<R> {
+        RandomAgent { rng }
+    }
+}
+
+impl<R> Agent for RandomAgent<R>
+where
+    R: Rng,
+{
+    fn act(&mut self, view: &View<'_>) -> StateInput {
+        match view.state() {
+            State::Bidding => {
+                let can_raise = matches!(view.bid(), Some((_, bid)) if bid < 200);
+                let raise = if can_raise && self.rng.gen_bool(1.0 / 3.0) {
+                    Fives::new(10)
+                } else {
+                    None
+                };
+                StateInput::Bidding(raise)
+            }
+            State::AdjustingBid => StateInput::AdjustingBid(Fives::zero()),
+            State::Distrubuting => {
+                let hand: Vec<_> = view
+                    .hand()
+                    .expect("Hands are dealt while distributing")
+                    .iter()
+                    .map(Card::description)
+                    .collect();
+                let passed: Vec<_> = hand.choose_multiple(&mut self.rng, 2).collect();
+                StateInput::Distrubuting(*passed[0], *passed[1])
+            }
+            State::Playing => {
+                let legal: Vec<_> = view.legal_cards().iter().collect();
+                let card = legal
+                    .choose(&mut self.rng)
+                    .expect("The player to move has a legal card");
+                StateInput::Playing(*card)
+            }
+            State::Finished => StateInput::Finished(self.rng.gen()),
+            State::GameOver => StateInput::GameOver(),
+        }
+    }
+}
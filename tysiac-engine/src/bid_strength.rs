@@ -0,0 +1,156 @@
+use crate::agent::{Agent, View};
+use crate::heuristic::{marriages, Difficulty, HeuristicAgent};
+use crate::Error;
+use card_games_lib::{CardSet, Pile, Seats};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use tysiac::{Card, Fives, Game, Player, SomeState, State, StateInput};
+
+/// The bids `BidEstimate::success` covers: every ten from the opening 100 up
+/// to all the card points and marriages there are.
+pub const BID_LEVELS: core::ops::RangeInclusive<usize> = 100..=360;
+
+/// How a hand did when played out as the bid winner against random
+/// opponent hands and prikups.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BidEstimate {
+    pub samples: usize,
+    pub expected_card_points: f64,
+    /// The average points of the marriages declared in play.
+    pub expected_marriage_points: f64,
+    /// How often the hand held a marriage once cards were passed.
+    pub marriage_chance: f64,
+    /// The chance of making each bid level, from 100 up in steps of ten.
+    pub success: Vec<(usize, f64)>,
+}
+
+impl BidEstimate {
+    /// The chance of making `bid`.
+    pub fn success(&self, bid: usize) -> f64 {
+        self.success
+            .iter()
+            .find(|(level, _)| *level >= bid)
+            .map_or(0.0, |(_, chance)| *chance)
+    }
+
+    /// The highest bid made at least `confidence` of the time.
+    pub fn max_bid(&self, confidence: f64) -> Option<usize> {
+        self.success
+            .iter()
+            .filter(|(_, chance)| *chance >= confidence)
+            .map(|(level, _)| *level)
+            .max()
+    }
+
+    /// The highest bid made at least half of the time.
+    pub fn recommended_max_bid(&self) -> Option<usize> {
+        self.max_bid(0.5)
+    }
+}
+
+/// Estimates how well `hand` would do as the bid winner by dealing the rest
+/// of the deck at random `samples` times and playing each deal out with
+/// `Hard` heuristics. A seven card hand also draws a random prikup; a ten
+/// card hand is taken to already hold it. Returns `None` for other hands,
+/// and an error if an agent makes an illegal move.
+pub fn estimate_bid<R>(
+    hand: &Pile<Card>,
+    samples: usize,
+    rng: &mut R,
+) -> Result<Option<BidEstimate>, Error>
+where
+    R: Rng,
+{
+    let held = CardSet::from(hand);
+    if held.len() != hand.len() || (hand.len() != 7 && hand.len() != 10) {
+        return Ok(None);
+    }
+
+    let deck: CardSet = card_games_lib::Deck::<card_games_lib::Card>::schnapsen()
+        .drain()
+        .collect();
+    let mut rest: Vec<Card> = (deck - held)
+        .iter()
+        .map(|c| Card::from_description(c).expect("The schnapsen deck only holds tysiac cards"))
+        .collect();
+    let hand: Vec<Card> = hand.iter().cloned().collect();
+
+    let mut card_points = 0;
+    let mut marriage_points = 0;
+    let mut with_marriage = 0;
+    let mut scores = Vec::with_capacity(samples);
+
+    for _ in 0..samples {
+        rest.shuffle(rng);
+        // A's seven, B's and C's, then the prikup
+        let (first, prikup) = hand.split_at(7);
+        let prikup = if prikup.is_empty() {
+            &rest[14..]
+        } else {
+            prikup
+        };
+        let mut deck = first.iter().chain(&rest[..14]).chain(prikup).cloned();
+
+        let (score, points, married) = play_out(SomeState::deal(&mut deck), rng.gen())?;
+        scores.push(score);
+        card_points += points;
+        marriage_points += score - points;
+        with_marriage += married as usize;
+    }
+
+    let samples_f = samples.max(1) as f64;
+    Ok(Some(BidEstimate {
+        samples,
+        expected_card_points: card_points as f64 / samples_f,
+        expected_marriage_points: marriage_points as f64 / samples_f,
+        marriage_chance: with_marriage as f64 / samples_f,
+        success: BID_LEVELS
+            .step_by(10)
+            .map(|level| {
+                let made = scores.iter().filter(|s| **s >= level as isize).count();
+                (level, made as f64 / samples_f)
+            })
+            .collect(),
+    }))
+}
+
+/// Lets A win the bid at 100 and plays the hand out, returning A's score,
+/// the card points within it, and whether A held a marriage in play.
+fn play_out(mut state: SomeState, seed: u64) -> Result<(isize, isize, bool), Error> {
+    let mut game = Game::default();
+    let mut agents = Seats::new(|player: Player| {
+        HeuristicAgent::new(
+            StdRng::seed_from_u64(seed ^ player as u64),
+            Difficulty::Hard,
+        )
+    });
+    let step = |state: SomeState, game: &mut Game, input| {
+        let (next, result) = state.step(game, input);
+        result.map(|_| next).map_err(Error::Game)
+    };
+
+    for input in [
+        StateInput::Bidding(None),
+        StateInput::Bidding(None),
+        StateInput::AdjustingBid(Fives::zero()),
+    ] {
+        state = step(state, &mut game, input)?;
+    }
+    let pass = agents[Player::A].act(&View::new(Player::A, &state, &game));
+    state = step(state, &mut game, pass)?;
+    let married = matches!(state.hand(&Player::A), Some(hand) if !marriages(hand).is_empty());
+
+    while state.state() != State::Finished {
+        let player = state.next_player();
+        let input = agents[player].act(&View::new(player, &state, &game));
+        state = step(state, &mut game, input)?;
+    }
+
+    let score = state.bidder_score().expect("A finished hand has a score");
+    let points = state
+        .taken()
+        .expect("A finished hand has tricks")
+        .cards_won_by(Player::A)
+        .map(|c| isize::from(c.rank().point_value()))
+        .sum();
+    Ok((score, points, married))
+}
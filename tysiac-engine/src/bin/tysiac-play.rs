@@ -0,0 +1,173 @@
+//! Plays tysiac in the terminal, against bots or hot-seat.
+//!
+//! ```text
+//! tysiac-play [--seed S] [--seats A,B,C]
+//! ```
+//!
+//! Each seat is `human` or one of the agents `tysiac-selfplay` takes. The
+//! default is a human in seat A against two `medium` bots.
+
+use card_games_lib::{Seat, Seats};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+use tysiac::{SomeState, State};
+use tysiac_engine::selfplay::AgentKind;
+use tysiac_engine::terminal::{describe_error, describe_input, help, parse_input, render_view};
+use tysiac_engine::{Agent, Tysiac};
+
+/// Clears the terminal and moves the cursor to the top left.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+enum Occupant {
+    Human,
+    Bot(Box<dyn Agent>),
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(u64, Vec<String>), String> {
+    let mut seed = rand::thread_rng().gen();
+    let mut seats = vec!["human".to_owned(), "medium".to_owned(), "medium".to_owned()];
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--seed" => {
+                seed = value
+                    .parse()
+                    .map_err(|_| format!("{:?} is not a seed", value))?
+            }
+            "--seats" => seats = value.split(',').map(str::to_owned).collect(),
+            _ => return Err(format!("Unknown argument {:?}", flag)),
+        }
+    }
+
+    if seats.len() != 3 {
+        return Err("--seats takes three seats".to_owned());
+    }
+    Ok((seed, seats))
+}
+
+fn main() {
+    let (seed, names) = parse_args(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(2);
+    });
+    let mut seats = Vec::new();
+    for (idx, name) in names.iter().enumerate() {
+        seats.push(match name.as_str() {
+            "human" => Occupant::Human,
+            agent => match agent.parse::<AgentKind>() {
+                Ok(agent) => {
+                    let rng = StdRng::seed_from_u64(seed.wrapping_add(1 + idx as u64));
+                    Occupant::Bot(agent.build(rng))
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    process::exit(2);
+                }
+            },
+        });
+    }
+    let mut seats = Seats::from_vec(seats);
+    let humans = names.iter().filter(|name| *name == "human").count();
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut prompt = |text: &str| -> Option<String> {
+        print!("{}", text);
+        io::stdout().flush().expect("Writing to stdout");
+        match lines.next() {
+            Some(Ok(line)) if line.trim() != "quit" => Some(line),
+            _ => None,
+        }
+    };
+
+    let mut deals = StdRng::seed_from_u64(seed);
+    let mut game = Tysiac::new(SomeState::random(&mut deals));
+    println!(
+        "Dealing with seed {}. Type help for help, quit to leave.",
+        seed
+    );
+
+    // Whether the last move was refused, leaving the same player to move
+    let mut refused = false;
+    loop {
+        let state = game.state().expect("The game always has a state");
+        let player = state.next_player();
+
+        match state.state() {
+            State::GameOver => {
+                println!(
+                    "{:?} wins with {} points!",
+                    player,
+                    game.game().points(player)
+                );
+                return;
+            }
+            State::Finished => {
+                let (bid_winner, bid) = state.bid().expect("A finished hand has a bid");
+                let score = state.bidder_score().unwrap_or(0);
+                println!(
+                    "{:?} scored {} against a bid of {}.",
+                    bid_winner, score, bid
+                );
+                if humans > 0 && prompt("Press enter to deal the next hand. ").is_none() {
+                    return;
+                }
+                game.deal(deals.gen())
+                    .expect("A finished hand always deals the next");
+                continue;
+            }
+            _ => {}
+        }
+
+        let input = match &mut seats[player] {
+            Occupant::Bot(agent) => {
+                let input = agent.act(&game.view(player).expect("The game always has a state"));
+                println!("{}", describe_input(player, &input));
+                input
+            }
+            Occupant::Human => {
+                let view = game.view(player).expect("The game always has a state");
+                if humans > 1 && !refused {
+                    // Hide the last player's hand before handing over
+                    print!("{}", CLEAR_SCREEN);
+                    let pass = format!("Pass the keyboard to {:?} and press enter. ", player);
+                    if prompt(&pass).is_none() {
+                        return;
+                    }
+                }
+                print!("{}", render_view(&view));
+
+                loop {
+                    let line = match prompt(&format!("{:?}> ", player)) {
+                        Some(line) => line,
+                        None => return,
+                    };
+                    if line.trim() == "help" {
+                        println!("{}", help(view.state()));
+                        continue;
+                    }
+                    match parse_input(&view, &line) {
+                        Ok(input) => break input,
+                        Err(error) => println!("{}", error),
+                    }
+                }
+            }
+        };
+
+        match game.feed(player.index(), input) {
+            Ok(()) => refused = false,
+            Err(error) => {
+                println!("{}", describe_error(&error));
+                // Asked again, a bot would likely make the same move
+                if let Occupant::Bot(_) = seats[player] {
+                    eprintln!("{:?}'s bot made a move the game refused", player);
+                    process::exit(1);
+                }
+                refused = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Plays tysiac matches between agents and prints statistics.
+//!
+//! ```text
+//! tysiac-selfplay [--matches N] [--seed S] [--threads T] [--max-hands H]
+//!                 [--agents A,B,C]
+//! ```
+//!
+//! Agents are `random`, `easy`, `medium`, `hard`, `mc:<deals>` or
+//! `mcdd:<deals>`.
+
+use std::env;
+use std::process;
+use std::str::FromStr;
+use tysiac_engine::selfplay::{self, AgentKind, Config};
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let mut config = Config::default();
+
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+
+        match flag.as_str() {
+            "--matches" => config.matches = number(value()?)?,
+            "--seed" => config.seed = number(value()?)?,
+            "--threads" => config.threads = number(value()?)?,
+            "--max-hands" => config.max_hands = number(value()?)?,
+            "--agents" => {
+                let agents = value()?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<Vec<AgentKind>, _>>()?;
+                config.agents = match agents.as_slice() {
+                    [agent] => [*agent; 3],
+                    [a, b, c] => [*a, *b, *c],
+                    _ => return Err("--agents takes one or three agents".to_owned()),
+                };
+            }
+            _ => return Err(format!("Unknown argument {:?}", flag)),
+        }
+    }
+
+    Ok(config)
+}
+
+fn number<T: FromStr>(value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{:?} is not a number", value))
+}
+
+fn main() {
+    let config = parse_args(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(2);
+    });
+
+    match selfplay::run(&config) {
+        Ok(stats) => print!("{}", stats),
+        Err(error) => {
+            eprintln!("A match failed: {:?}", error);
+            process::exit(1);
+        }
+    }
+}
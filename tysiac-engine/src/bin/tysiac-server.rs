@@ -0,0 +1,73 @@
+//! Hosts tysiac tables over TCP, and over WebSocket if given `--ws`.
+//!
+//! ```text
+//! tysiac-server [--addr 127.0.0.1:7878] [--ws 127.0.0.1:7879] [--seed S]
+//! ```
+//!
+//! Clients send and receive one message per line, e.g. `create lunch quick
+//! Ada`, `ready`, `bid 10` or `play QH`, until they switch to JSON or binary
+//! frames with `hello 2 json` or `hello 2 binary`. Tables start once every
+//! seat is taken and ready; `bots` fills the empty ones. WebSocket clients
+//! send the same messages, one per WebSocket message. See
+//! `tysiac_engine::protocol`.
+
+use rand::Rng;
+use std::env;
+use std::net::TcpListener;
+use std::process;
+use std::thread;
+use tysiac_engine::server::{Hub, Server};
+
+fn bind(addr: &str) -> TcpListener {
+    let listener = TcpListener::bind(addr).unwrap_or_else(|error| {
+        eprintln!("Cannot listen on {}: {}", addr, error);
+        process::exit(1);
+    });
+    println!("Listening on {}", addr);
+    listener
+}
+
+fn main() {
+    let mut addr = "127.0.0.1:7878".to_owned();
+    let mut ws = None;
+    let mut seed = rand::thread_rng().gen();
+
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next().unwrap_or_else(|| {
+            eprintln!("{} needs a value", flag);
+            process::exit(2);
+        });
+        match flag.as_str() {
+            "--addr" => addr = value,
+            "--ws" => ws = Some(value),
+            "--seed" => {
+                seed = value.parse().unwrap_or_else(|_| {
+                    eprintln!("{:?} is not a seed", value);
+                    process::exit(2);
+                })
+            }
+            _ => {
+                eprintln!("Unknown argument {:?}", flag);
+                process::exit(2);
+            }
+        }
+    }
+
+    let server = Server::new(Hub::new(seed));
+    if let Some(ws) = ws {
+        let listener = bind(&ws);
+        let server = server.clone();
+        thread::spawn(move || {
+            if let Err(error) = server.serve_websocket(listener) {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        });
+    }
+
+    if let Err(error) = server.serve(bind(&addr)) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,267 @@
+use card_games_lib::{Card, CardSet, Seat, Suit, Trick};
+use std::collections::HashMap;
+use tysiac::{Player, SomeState, TRICK_RULES};
+
+const INFINITY: isize = 10_000;
+
+/// The bid winner, or the two players against them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Side {
+    Bidder,
+    Defenders,
+}
+
+impl Side {
+    pub fn of(player: Player, bid_winner: Player) -> Side {
+        if player == bid_winner {
+            Side::Bidder
+        } else {
+            Side::Defenders
+        }
+    }
+}
+
+/// The points each side can be sure of taking in the rest of the hand when
+/// the other side defends perfectly: the card points of the tricks it wins
+/// and the marriages it declares.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub bidder: isize,
+    pub defenders: isize,
+}
+
+/// Solves the card play of a hand with every hand in view, by alpha-beta
+/// search over the remaining tricks. Positions at the start of a trick are
+/// kept in a transposition table, so later queries on the same hand are
+/// cheaper.
+pub struct DoubleDummy {
+    bid_winner: Player,
+    position: Position,
+    table: HashMap<Key, Bound>,
+}
+
+#[derive(Debug, Clone)]
+struct Position {
+    hands: [CardSet; 3],
+    trick: Trick<Player, Card>,
+    to_move: Player,
+    trump: Option<Suit>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    side: Side,
+    hands: [u64; 3],
+    to_move: Player,
+    trump: Option<Suit>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Bound {
+    lower: isize,
+    upper: isize,
+}
+
+impl DoubleDummy {
+    /// Takes the position of a `Playing` state, or `None` for other states.
+    pub fn new(state: &SomeState) -> Option<DoubleDummy> {
+        let played = state.trick()?;
+        let (bid_winner, _) = state.bid()?;
+        let mut hands = [CardSet::EMPTY; 3];
+        for player in Player::all() {
+            hands[player.index()] = CardSet::from(state.hand(&player)?);
+        }
+
+        let mut trick = Trick::new(played.leader());
+        for (player, card) in played.plays() {
+            trick.play(*player, card.description());
+        }
+
+        Some(DoubleDummy {
+            bid_winner,
+            position: Position {
+                hands,
+                trick,
+                to_move: state.next_player(),
+                trump: state.trump().map(tysiac::Suit::description),
+            },
+            table: HashMap::new(),
+        })
+    }
+
+    pub fn solve(&mut self) -> Solution {
+        Solution {
+            bidder: self.points(Side::Bidder),
+            defenders: self.points(Side::Defenders),
+        }
+    }
+
+    /// The most points `side` can take from here on.
+    pub fn points(&mut self, side: Side) -> isize {
+        self.search(side, self.position.clone(), -INFINITY, INFINITY)
+    }
+
+    /// The cards the player to move may play.
+    pub fn legal_cards(&self) -> CardSet {
+        self.position.legal_cards()
+    }
+
+    /// Plays `card` for the player to move, keeping what was learnt about
+    /// the positions that can still be reached.
+    pub fn play(&mut self, card: Card) {
+        self.position.play(card);
+    }
+
+    /// The most points `side` can take after each card the player to move
+    /// may play.
+    pub fn card_values(&mut self, side: Side) -> Vec<(Card, isize)> {
+        let position = self.position.clone();
+        position
+            .legal_cards()
+            .iter()
+            .map(|card| {
+                let mut next = position.clone();
+                let gain = self.gain(side, next.play(card));
+                (card, gain + self.search(side, next, -INFINITY, INFINITY))
+            })
+            .collect()
+    }
+
+    fn gain(&self, side: Side, gains: [isize; 3]) -> isize {
+        Player::all()
+            .filter(|player| Side::of(*player, self.bid_winner) == side)
+            .map(|player| gains[player.index()])
+            .sum()
+    }
+
+    fn search(
+        &mut self,
+        side: Side,
+        position: Position,
+        mut alpha: isize,
+        mut beta: isize,
+    ) -> isize {
+        let key = if position.trick.is_empty() {
+            if position.hands.iter().all(|hand| hand.is_empty()) {
+                return 0;
+            }
+
+            let key = Key {
+                side,
+                hands: [
+                    position.hands[0].bits(),
+                    position.hands[1].bits(),
+                    position.hands[2].bits(),
+                ],
+                to_move: position.to_move,
+                trump: position.trump,
+            };
+            if let Some(bound) = self.table.get(&key) {
+                if bound.lower >= beta || bound.lower == bound.upper {
+                    return bound.lower;
+                }
+                if bound.upper <= alpha {
+                    return bound.upper;
+                }
+                alpha = alpha.max(bound.lower);
+                beta = beta.min(bound.upper);
+            }
+            Some(key)
+        } else {
+            None
+        };
+
+        let (first_alpha, first_beta) = (alpha, beta);
+        let maximizing = Side::of(position.to_move, self.bid_winner) == side;
+        let mut best = if maximizing { -INFINITY } else { INFINITY };
+
+        let mut cards: Vec<Card> = position.legal_cards().iter().collect();
+        cards.sort_by_key(|card| core::cmp::Reverse(points(*card)));
+        for card in cards {
+            let mut next = position.clone();
+            let gain = self.gain(side, next.play(card));
+            let value = gain + self.search(side, next, alpha - gain, beta - gain);
+
+            if maximizing {
+                best = best.max(value);
+                alpha = alpha.max(value);
+            } else {
+                best = best.min(value);
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let Some(key) = key {
+            let bound = self.table.entry(key).or_insert(Bound {
+                lower: -INFINITY,
+                upper: INFINITY,
+            });
+            if best <= first_alpha {
+                bound.upper = best;
+            } else if best >= first_beta {
+                bound.lower = best;
+            } else {
+                *bound = Bound {
+                    lower: best,
+                    upper: best,
+                };
+            }
+        }
+
+        best
+    }
+}
+
+impl Position {
+    fn legal_cards(&self) -> CardSet {
+        let hand = self.hands[self.to_move.index()];
+        TRICK_RULES.legal_plays(&self.trick, self.trump, hand)
+    }
+
+    /// Plays `card` for the player to move, returning the points each player
+    /// gained by it.
+    fn play(&mut self, card: Card) -> [isize; 3] {
+        let player = self.to_move;
+        let hand = &mut self.hands[player.index()];
+        hand.remove(card);
+        let mut gains = [0; 3];
+
+        if self.trick.is_empty()
+            && is_weddable(card)
+            && hand.suit(card.suit()).iter().any(is_weddable)
+        {
+            self.trump = Some(card.suit());
+            gains[player.index()] += marriage_value(card.suit());
+        }
+
+        self.trick.play(player, card);
+        self.to_move = player.next();
+
+        if self.trick.len() == 3 {
+            let winner = self
+                .trick
+                .winner(TRICK_RULES.order, self.trump)
+                .expect("A full trick has a winner");
+            gains[winner.index()] += self.trick.cards().copied().map(points).sum::<isize>();
+            self.trick = Trick::new(winner);
+            self.to_move = winner;
+        }
+
+        gains
+    }
+}
+
+fn is_weddable(card: Card) -> bool {
+    matches!(tysiac::Rank::from_description(card.rank()), Some(rank) if rank.is_weddable())
+}
+
+fn points(card: Card) -> isize {
+    tysiac::Rank::from_description(card.rank()).map_or(0, |rank| rank.point_value().into())
+}
+
+fn marriage_value(suit: Suit) -> isize {
+    tysiac::Suit::from_description(suit).map_or(0, |suit| suit.marriage_value().into())
+}
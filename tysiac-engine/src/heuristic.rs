@@ -0,0 +1,217 @@
+use crate::agent::{Agent, View};
+use card_games_lib::{Pile, RankOrder, TrickOrder, TysiacOrder};
+use rand::{seq::SliceRandom, Rng};
+use tysiac::{Card, Fives, Rank, State, StateInput, Suit};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// The knobs of `HeuristicAgent`. Values are in game points.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Heuristics {
+    /// Added to the value of the seven card hand for the unseen prikup.
+    pub prikup_allowance: usize,
+    /// Added to the hand value before deciding how high to bid. Negative
+    /// values bid cautiously.
+    pub optimism: isize,
+    /// Raise the bid after taking the prikup if the hand is worth more.
+    pub adjust_bid: bool,
+    /// The chance of playing a random legal card instead of the chosen one.
+    pub blunder_chance: f64,
+}
+
+impl Heuristics {
+    pub fn new(difficulty: Difficulty) -> Heuristics {
+        match difficulty {
+            Difficulty::Easy => Heuristics {
+                prikup_allowance: 0,
+                optimism: -20,
+                adjust_bid: false,
+                blunder_chance: 0.3,
+            },
+            Difficulty::Medium => Heuristics {
+                prikup_allowance: 10,
+                optimism: 0,
+                adjust_bid: false,
+                blunder_chance: 0.1,
+            },
+            Difficulty::Hard => Heuristics {
+                prikup_allowance: 15,
+                optimism: 10,
+                adjust_bid: true,
+                blunder_chance: 0.0,
+            },
+        }
+    }
+
+    /// What the hand is expected to score: its card points, its marriages,
+    /// and a bonus for the aces and tens that should take tricks.
+    pub fn hand_value(&self, hand: &Pile<Card>) -> usize {
+        let points: usize = hand
+            .iter()
+            .map(|c| usize::from(c.rank().point_value()))
+            .sum();
+        let marriages: usize = marriages(hand)
+            .iter()
+            .map(|suit| usize::from(suit.marriage_value()))
+            .sum();
+        let winners = hand
+            .iter()
+            .filter(|c| c.rank() == &Rank::Ace || c.rank() == &Rank::Ten)
+            .count();
+
+        points + marriages + 5 * winners
+    }
+
+    /// The highest bid worth making, a multiple of ten.
+    pub fn max_bid(&self, hand: &Pile<Card>, allowance: usize) -> usize {
+        let value = (self.hand_value(hand) + allowance) as isize + self.optimism;
+        value.max(0) as usize / 10 * 10
+    }
+
+    /// The two cheapest cards that are not part of a marriage.
+    pub fn cards_to_pass(
+        &self,
+        hand: &Pile<Card>,
+    ) -> Option<(card_games_lib::Card, card_games_lib::Card)> {
+        let marriages = marriages(hand);
+        let mut cards: Vec<&Card> = hand.iter().collect();
+        cards.sort_by_key(|c| {
+            let in_marriage = c.rank().is_weddable() && marriages.contains(c.suit());
+            (in_marriage, c.rank().point_value())
+        });
+
+        match cards.as_slice() {
+            [first, second, ..] => Some((first.description(), second.description())),
+            _ => None,
+        }
+    }
+
+    /// The card to play from `legal`: a marriage or an ace when leading,
+    /// otherwise the cheapest card that takes the trick, or the cheapest
+    /// card if none does.
+    pub fn card_to_play(
+        &self,
+        view: &View<'_>,
+        legal: &[card_games_lib::Card],
+    ) -> Option<card_games_lib::Card> {
+        let hand = view.hand()?;
+        let trick = view.trick()?;
+        let trump = view.trump().map(Suit::description);
+        let cheapest = |cards: &mut dyn Iterator<Item = card_games_lib::Card>| {
+            cards.min_by_key(|c| (points(*c), TysiacOrder.strength(c.rank())))
+        };
+
+        let winning = match trick.winning(TysiacOrder, trump) {
+            None => {
+                let marriage = marriages(hand)
+                    .into_iter()
+                    .filter(|suit| Some(suit.description()) != trump)
+                    .max_by_key(Suit::marriage_value);
+                let queen = marriage.map(|suit| {
+                    card_games_lib::Card(card_games_lib::Rank::Queen, suit.description())
+                });
+                let ace = legal.iter().find(|c| c.rank() == card_games_lib::Rank::Ace);
+
+                return queen
+                    .filter(|c| legal.contains(c))
+                    .or_else(|| ace.copied())
+                    .or_else(|| cheapest(&mut legal.iter().copied()));
+            }
+            Some((_, winning)) => winning.description(),
+        };
+
+        let lead = trick.lead_suit().expect("A trick with a winner has a lead");
+        let order = TrickOrder::new(TysiacOrder, lead, trump);
+        cheapest(&mut legal.iter().copied().filter(|c| order.beats(*c, winning)))
+            .or_else(|| cheapest(&mut legal.iter().copied()))
+    }
+}
+
+/// Bids from the strength of its hand, keeps marriages when passing cards,
+/// leads marriages to set trump and takes tricks as cheaply as it can.
+pub struct HeuristicAgent<R> {
+    rng: R,
+    heuristics: Heuristics,
+}
+
+impl<R> HeuristicAgent<R>
+where
+    R: Rng,
+{
+    pub fn new(rng: R, difficulty: Difficulty) -> HeuristicAgent<R> {
+        HeuristicAgent::with_heuristics(rng, Heuristics::new(difficulty))
+    }
+
+    pub fn with_heuristics(rng: R, heuristics: Heuristics) -> HeuristicAgent<R> {
+        HeuristicAgent { rng, heuristics }
+    }
+}
+
+impl<R> Agent for HeuristicAgent<R>
+where
+    R: Rng,
+{
+    fn act(&mut self, view: &View<'_>) -> StateInput {
+        let h = &self.heuristics;
+        let hand = view.hand();
+
+        match view.state() {
+            State::Bidding => {
+                let bid = view.bid().map_or(0, |(_, bid)| bid);
+                let limit = hand.map_or(0, |hand| h.max_bid(hand, h.prikup_allowance));
+                StateInput::Bidding(if bid + 10 <= limit {
+                    Fives::new(10)
+                } else {
+                    None
+                })
+            }
+            State::AdjustingBid => {
+                let bid = view.bid().map_or(0, |(_, bid)| bid);
+                let limit = hand.map_or(0, |hand| h.max_bid(hand, 0));
+                let increase = if h.adjust_bid && limit > bid {
+                    limit - bid
+                } else {
+                    0
+                };
+                StateInput::AdjustingBid(Fives::new(increase).unwrap_or_else(Fives::zero))
+            }
+            State::Distrubuting => {
+                let (next, prev) = hand
+                    .and_then(|hand| h.cards_to_pass(hand))
+                    .expect("The bid winner holds ten cards");
+                StateInput::Distrubuting(next, prev)
+            }
+            State::Playing => {
+                let legal: Vec<_> = view.legal_cards().iter().collect();
+                let card = if self.rng.gen_bool(h.blunder_chance) {
+                    legal.choose(&mut self.rng).copied()
+                } else {
+                    h.card_to_play(view, &legal)
+                };
+                StateInput::Playing(card.expect("The player to move has a legal card"))
+            }
+            State::Finished => StateInput::Finished(self.rng.gen()),
+            State::GameOver => StateInput::GameOver(),
+        }
+    }
+}
+
+pub(crate) fn marriages(hand: &Pile<Card>) -> Vec<Suit> {
+    Suit::ALL
+        .iter()
+        .filter(|suit| {
+            let holds = |rank: Rank| hand.iter().any(|c| c.suit() == *suit && c.rank() == &rank);
+            holds(Rank::King) && holds(Rank::Queen)
+        })
+        .cloned()
+        .collect()
+}
+
+fn points(card: card_games_lib::Card) -> u8 {
+    Rank::from_description(card.rank()).map_or(0, |rank| rank.point_value())
+}
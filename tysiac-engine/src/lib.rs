@@ -1,5 +1,23 @@
+use card_games_lib::{Seat, Seats};
 use tysiac::{Game, Player, SomeState, State, StateError, StateInput};
 
+mod agent;
+mod bid_strength;
+mod double_dummy;
+mod heuristic;
+pub mod lobby;
+mod monte_carlo;
+pub mod protocol;
+pub mod selfplay;
+pub mod server;
+pub mod terminal;
+
+pub use agent::*;
+pub use bid_strength::*;
+pub use double_dummy::*;
+pub use heuristic::*;
+pub use monte_carlo::*;
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum Error {
     Game(card_games_lib::Error<StateError, State>),
@@ -16,6 +34,51 @@ pub struct Tysiac {
 }
 
 impl Tysiac {
+    pub fn new(state: SomeState) -> Tysiac {
+        Tysiac {
+            game: Game::default(),
+            state: Some(state),
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn state(&self) -> Option<&SomeState> {
+        self.state.as_ref()
+    }
+
+    pub fn view(&self, seat: Player) -> Option<View<'_>> {
+        self.state
+            .as_ref()
+            .map(|state| View::new(seat, state, &self.game))
+    }
+
+    /// Asks the agent of the player to move for an input, and feeds it.
+    pub fn turn<A>(&mut self, agents: &mut Seats<Player, A>) -> Result<(), Error>
+    where
+        A: Agent,
+    {
+        let player = self.state().ok_or(Error::NoState)?.next_player();
+        let input = agents[player].act(&self.view(player).ok_or(Error::NoState)?);
+        self.feed(player.index(), input)
+    }
+
+    /// Plays turns until the hand is finished or the game is over.
+    pub fn play_hand<A>(&mut self, agents: &mut Seats<Player, A>) -> Result<(), Error>
+    where
+        A: Agent,
+    {
+        loop {
+            match self.state().map(SomeState::state) {
+                Some(State::Finished) | Some(State::GameOver) => return Ok(()),
+                Some(_) => self.turn(agents)?,
+                None => return Err(Error::NoState),
+            }
+        }
+    }
+
     pub fn feed(&mut self, player: usize, packet: StateInput) -> Result<(), Error> {
         if let Some(state) = self.state.take() {
             let next_player = state.next_player();
@@ -28,9 +91,16 @@ impl Tysiac {
                 });
             }
 
-            let (state, error) = state.step(&mut self.game, packet);
+            if let (SomeState::Finished(_), StateInput::Finished(_)) = (&state, &packet) {
+                self.state = Some(state);
 
-            dbg!(state.state());
+                let message = "The host deals the next hand".to_owned();
+                return Err(Error::Game(card_games_lib::Error::StepError(
+                    StateError::Finished(message),
+                )));
+            }
+
+            let (state, error) = state.step(&mut self.game, packet);
 
             self.state = Some(state);
             error.map_err(|x| Error::Game(x))
@@ -38,6 +108,16 @@ impl Tysiac {
             Err(Error::NoState)
         }
     }
+
+    /// Deals the next hand once the last is finished. Only the host deals,
+    /// so that no player chooses the shuffle.
+    pub fn deal(&mut self, seed: u64) -> Result<(), Error> {
+        let state = self.state.take().ok_or(Error::NoState)?;
+        let (state, error) = state.step(&mut self.game, StateInput::Finished(seed));
+
+        self.state = Some(state);
+        error.map_err(Error::Game)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,242 @@
+use crate::agent::Agent;
+use crate::heuristic::{Difficulty, HeuristicAgent};
+use crate::protocol::{Preset, SeatInfo, TableInfo};
+use crate::server::ConnectionId;
+use crate::terminal::{describe_error, describe_input};
+use crate::Tysiac;
+use card_games_lib::{Seat, Seats};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tysiac::{Player, SomeState, State};
+
+/// What a preset changes. Scoring and the game to 1000 are the same for
+/// every preset.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Ends a match after this many hands, the highest score winning.
+    pub hand_limit: Option<usize>,
+    /// How well bots seated at the table play.
+    pub bots: Difficulty,
+}
+
+impl Preset {
+    pub fn rules(self) -> Rules {
+        match self {
+            Preset::Standard => Rules {
+                hand_limit: None,
+                bots: Difficulty::Hard,
+            },
+            Preset::Quick => Rules {
+                hand_limit: Some(5),
+                bots: Difficulty::Medium,
+            },
+            Preset::Casual => Rules {
+                hand_limit: None,
+                bots: Difficulty::Easy,
+            },
+        }
+    }
+}
+
+pub(crate) enum Occupant {
+    Human {
+        name: String,
+        token: String,
+        connection: Option<ConnectionId>,
+        ready: bool,
+    },
+    Bot(Box<HeuristicAgent<StdRng>>),
+}
+
+/// A table in the lobby: who sits where, who watches, and the match once
+/// every seat is ready.
+pub(crate) struct Table {
+    pub(crate) name: String,
+    pub(crate) preset: Preset,
+    pub(crate) game: Tysiac,
+    pub(crate) seats: Seats<Player, Option<Occupant>>,
+    pub(crate) spectators: Vec<ConnectionId>,
+    pub(crate) started: bool,
+    hands: usize,
+}
+
+impl Table {
+    pub(crate) fn new(name: String, preset: Preset, deal: SomeState) -> Table {
+        Table {
+            name,
+            preset,
+            game: Tysiac::new(deal),
+            seats: Seats::new(|_| None),
+            spectators: vec![],
+            started: false,
+            hands: 0,
+        }
+    }
+
+    pub(crate) fn info(&self) -> TableInfo {
+        let seat = |player: Player| match &self.seats[player] {
+            None => SeatInfo::Empty,
+            Some(Occupant::Bot(_)) => SeatInfo::Bot,
+            Some(Occupant::Human { name, ready, .. }) => SeatInfo::Human {
+                name: name.clone(),
+                ready: *ready,
+            },
+        };
+
+        TableInfo {
+            name: self.name.clone(),
+            preset: self.preset,
+            started: self.started,
+            spectators: self.spectators.len(),
+            seats: [seat(Player::A), seat(Player::B), seat(Player::C)],
+        }
+    }
+
+    pub(crate) fn free_seat(&self) -> Option<Player> {
+        Player::all().find(|player| self.seats[*player].is_none())
+    }
+
+    pub(crate) fn humans(&self) -> usize {
+        self.seats
+            .iter()
+            .filter(|(_, o)| matches!(o, Some(Occupant::Human { .. })))
+            .count()
+    }
+
+    /// Whether nobody could come back to the table.
+    pub(crate) fn is_abandoned(&self) -> bool {
+        self.humans() == 0 && self.spectators.is_empty()
+    }
+
+    /// The connected players by seat, then the spectators with no seat.
+    pub(crate) fn connections(&self) -> impl Iterator<Item = (Option<Player>, ConnectionId)> + '_ {
+        let players = self
+            .seats
+            .iter()
+            .filter_map(|(player, occupant)| match occupant {
+                Some(Occupant::Human {
+                    connection: Some(connection),
+                    ..
+                }) => Some((Some(player), *connection)),
+                _ => None,
+            });
+        players.chain(self.spectators.iter().map(|connection| (None, *connection)))
+    }
+
+    pub(crate) fn bot<R: Rng>(&self, rng: &mut R) -> Occupant {
+        let rng = StdRng::seed_from_u64(rng.gen());
+        Occupant::Bot(Box::new(HeuristicAgent::new(rng, self.preset.rules().bots)))
+    }
+
+    pub(crate) fn fill_bots<R: Rng>(&mut self, rng: &mut R) {
+        while let Some(seat) = self.free_seat() {
+            self.seats[seat] = Some(self.bot(rng));
+        }
+    }
+
+    /// Every seat is taken and every player in them is ready.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.seats.iter().all(|(_, occupant)| match occupant {
+            Some(Occupant::Human { ready, .. }) => *ready,
+            Some(Occupant::Bot(_)) => true,
+            None => false,
+        })
+    }
+
+    pub(crate) fn start<R: Rng>(&mut self, rng: &mut R) {
+        self.game = Tysiac::new(SomeState::random(rng));
+        self.started = true;
+        self.hands = 0;
+    }
+
+    /// Plays the bots' turns and deals each next hand until a player is to
+    /// move or the match is over, returning what happened. While anyone is
+    /// still at the table, bots also play for the players who lost their
+    /// connection. A move or deal the game refuses ends the match.
+    pub(crate) fn advance<R: Rng>(&mut self, rng: &mut R) -> Vec<String> {
+        let mut events = vec![];
+        let watched = self.connections().next().is_some();
+
+        while self.started {
+            let state = self.game.state().expect("Tables always have a state");
+            let player = state.next_player();
+            match state.state() {
+                State::GameOver => {
+                    events.push(format!("{:?} wins the match", player));
+                    self.finish();
+                }
+                State::Finished => {
+                    let (bid_winner, bid) = state.bid().expect("A finished hand has a bid");
+                    let score = state.bidder_score().unwrap_or(0);
+                    events.push(format!(
+                        "{:?} scored {} against a bid of {}",
+                        bid_winner, score, bid
+                    ));
+                    self.hands += 1;
+
+                    if self.preset.rules().hand_limit == Some(self.hands) {
+                        let points = |player: &Player| self.game.game().points(*player);
+                        let best = Player::all().map(|p| points(&p)).max();
+                        let leaders: Vec<String> = Player::all()
+                            .filter(|p| Some(points(p)) == best)
+                            .map(|p| format!("{:?}", p))
+                            .collect();
+                        events.push(match leaders.as_slice() {
+                            [winner] => {
+                                format!("{} wins the match after {} hands", winner, self.hands)
+                            }
+                            _ => format!(
+                                "{} tie the match after {} hands",
+                                leaders.join(" and "),
+                                self.hands
+                            ),
+                        });
+                        self.finish();
+                    } else if let Err(error) = self.game.deal(rng.gen()) {
+                        events.push(format!(
+                            "The match ends, as the next hand cannot be dealt: {}",
+                            describe_error(&error)
+                        ));
+                        self.finish();
+                    }
+                }
+                _ => {
+                    let view = self.game.view(player).expect("Checked above");
+                    let input = match &mut self.seats[player] {
+                        Some(Occupant::Bot(agent)) => agent.act(&view),
+                        Some(Occupant::Human {
+                            connection: None, ..
+                        }) if watched => {
+                            let rng = StdRng::seed_from_u64(rng.gen());
+                            HeuristicAgent::new(rng, self.preset.rules().bots).act(&view)
+                        }
+                        _ => break,
+                    };
+                    let event = describe_input(player, &input);
+                    match self.game.feed(player.index(), input) {
+                        Ok(()) => events.push(event),
+                        Err(error) => {
+                            events.push(format!(
+                                "The match ends, as the bot playing {:?} was refused: {}",
+                                player,
+                                describe_error(&error)
+                            ));
+                            self.finish();
+                        }
+                    }
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Back to waiting for everyone to be ready.
+    fn finish(&mut self) {
+        self.started = false;
+        for (_, occupant) in self.seats.iter_mut() {
+            if let Some(Occupant::Human { ready, .. }) = occupant {
+                *ready = false;
+            }
+        }
+    }
+}
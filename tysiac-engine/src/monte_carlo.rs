@@ -0,0 +1,326 @@
+use crate::agent::{Agent, View};
+use crate::double_dummy::{DoubleDummy, Side};
+use crate::heuristic::{Difficulty, HeuristicAgent, Heuristics};
+use card_games_lib::{CardSet, Deck, Pile, Seat, Seats};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use std::time::{Duration, Instant};
+use tysiac::{Card, Game, Player, SomeState, State, StateInput};
+
+/// The cards the bid winner could pass to one defender, once they have
+/// passed one of their ten to the other.
+const PASS_CHOICES: usize = 9;
+
+/// How long `MonteCarloAgent` searches before choosing a card.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Budget {
+    /// The number of deals to sample.
+    Iterations(usize),
+    /// Sample deals until the time is up. At least one deal is sampled.
+    Time(Duration),
+}
+
+/// How `MonteCarloAgent` scores a card in a sampled deal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Evaluator {
+    /// Play the hand out with the `Hard` heuristics and count the points
+    /// the seat's side took.
+    Rollout,
+    /// The points the seat's side can take with `DoubleDummy` play.
+    DoubleDummy,
+}
+
+/// What the seat has seen of the cards it does not hold: the cards played
+/// so far, the suits each player has shown to be out of by not following,
+/// and where the prikup and passed cards went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Knowledge {
+    seat: Player,
+    hand: CardSet,
+    unseen: CardSet,
+    voids: Seats<Player, CardSet>,
+    known: Seats<Player, CardSet>,
+    /// Whether one of the prikup cards known to be with the bid winner may
+    /// have been passed to the other defender instead.
+    loose: bool,
+    hand_lens: Seats<Player, usize>,
+}
+
+impl Knowledge {
+    pub fn new(view: &View<'_>) -> Knowledge {
+        let seat = view.seat();
+        let hand = view.hand().map(CardSet::from).unwrap_or(CardSet::EMPTY);
+        let mut played = CardSet::EMPTY;
+        let mut voids = Seats::new(|_| CardSet::EMPTY);
+        let mut known = Seats::new(|_| CardSet::EMPTY);
+        let mut loose = false;
+
+        // The bid winner keeps the prikup but for the cards they pass. Only
+        // they know which cards went to whom; a defender knows which card
+        // they received, and that at most one other went to their partner.
+        let prikup = view.prikup().unwrap_or(CardSet::EMPTY);
+        let bid_winner = view.bid().map(|(bid_winner, _)| bid_winner);
+        match (bid_winner, view.passed()) {
+            (_, Some(passed)) => known = passed.clone(),
+            (Some(bid_winner), None) => {
+                known[bid_winner] = prikup;
+                loose = view.state() == State::Playing && !prikup.is_empty();
+            }
+            (None, None) => {}
+        }
+
+        let taken = view
+            .taken()
+            .into_iter()
+            .flat_map(|t| t.iter().map(|(_, t)| t));
+        for trick in taken.chain(view.trick()) {
+            let lead = trick.lead_suit();
+            for (player, card) in trick.plays() {
+                let card = card.description();
+                played.insert(card);
+                if prikup.contains(card) && Some(*player) != bid_winner && *player != seat {
+                    // The card passed to the partner is accounted for
+                    loose = false;
+                }
+                if let Some(lead) = lead.filter(|lead| *lead != card.suit()) {
+                    voids[*player] |= CardSet::of_suit(lead);
+                }
+            }
+        }
+
+        let deck: CardSet = Deck::<card_games_lib::Card>::schnapsen().drain().collect();
+        let unseen = deck - hand - played;
+        Knowledge {
+            seat,
+            hand,
+            unseen,
+            voids,
+            known: known.map(|_, cards| *cards & unseen),
+            loose,
+            hand_lens: Seats::new(|player| view.hand_len(player)),
+        }
+    }
+
+    /// The cards held by the other players.
+    pub fn unseen(&self) -> CardSet {
+        self.unseen
+    }
+
+    /// The suits `player` is known not to hold, as the cards of those suits.
+    pub fn voids(&self, player: Player) -> CardSet {
+        self.voids[player]
+    }
+
+    /// The unseen cards known to be in `player`'s hand, from the prikup and
+    /// the cards passed after the bid.
+    pub fn known(&self, player: Player) -> CardSet {
+        self.known[player]
+    }
+
+    /// Deals the unseen cards to the other players, giving each the cards
+    /// known to be theirs and keeping them out of the suits they are void
+    /// in. The cards fewest players can hold are dealt first, each to a
+    /// player chosen in proportion to the room left in their hand.
+    ///
+    /// While the other defender's passed card is unknown, each prikup card
+    /// still with the bid winner is taken to be it as often as any other
+    /// card the bid winner could have passed.
+    pub fn sample<R>(&self, rng: &mut R) -> Seats<Player, CardSet>
+    where
+        R: Rng,
+    {
+        let others = || Player::all().filter(move |player| *player != self.seat);
+        let takers = |card| others().filter(move |p| !self.voids[*p].contains(card));
+
+        let mut known = self.known.clone();
+        if self.loose {
+            let prikup: Vec<_> = known.iter().flat_map(|(_, cards)| cards.iter()).collect();
+            if let Some(card) = prikup.get(rng.gen_range(0, PASS_CHOICES)) {
+                for (_, cards) in known.iter_mut() {
+                    cards.remove(*card);
+                }
+            }
+        }
+        let pinned = known
+            .iter()
+            .fold(CardSet::EMPTY, |all, (_, cards)| all | *cards);
+
+        let mut cards: Vec<_> = (self.unseen - pinned).iter().collect();
+        cards.shuffle(rng);
+        cards.sort_by_key(|card| takers(*card).count());
+
+        let mut hands = Seats::new(|player| {
+            if player == self.seat {
+                self.hand
+            } else {
+                known[player]
+            }
+        });
+        for card in cards {
+            let room =
+                |player: &Player| self.hand_lens[*player].saturating_sub(hands[*player].len());
+            let choices: Vec<Player> = takers(card).filter(|p| room(p) > 0).collect();
+            let choices = if choices.is_empty() {
+                // What was seen contradicts itself; ignore the voids
+                others().filter(|p| room(p) > 0).collect()
+            } else {
+                choices
+            };
+            let player = *choices
+                .choose_weighted(rng, room)
+                .expect("The unseen cards fill the other hands");
+            hands[player].insert(card);
+        }
+
+        hands
+    }
+}
+
+/// Plays cards by determinized Monte Carlo search: it samples deals of the
+/// hidden cards consistent with what its seat has seen, scores each legal
+/// card in every deal with its `Evaluator`, and picks the card that did
+/// best on average. Outside of play it falls back to `HeuristicAgent`.
+pub struct MonteCarloAgent<R> {
+    rng: R,
+    budget: Budget,
+    evaluator: Evaluator,
+    rollout: Heuristics,
+    fallback: HeuristicAgent<R>,
+}
+
+impl<R> MonteCarloAgent<R>
+where
+    R: Rng + SeedableRng,
+{
+    pub fn new(rng: R, budget: Budget) -> MonteCarloAgent<R> {
+        MonteCarloAgent::with_evaluator(rng, budget, Evaluator::Rollout)
+    }
+
+    pub fn with_evaluator(mut rng: R, budget: Budget, evaluator: Evaluator) -> MonteCarloAgent<R> {
+        MonteCarloAgent {
+            fallback: HeuristicAgent::new(R::seed_from_u64(rng.gen()), Difficulty::Hard),
+            rng,
+            budget,
+            evaluator,
+            rollout: Heuristics::new(Difficulty::Hard),
+        }
+    }
+
+    /// The average score of each legal card over the sampled deals, as
+    /// given by the evaluator.
+    pub fn evaluate(&mut self, view: &View<'_>) -> Vec<(card_games_lib::Card, f64)> {
+        let legal: Vec<_> = view.legal_cards().iter().collect();
+        let playing = match view.playing() {
+            Some(playing) if !legal.is_empty() => playing,
+            _ => return vec![],
+        };
+        let knowledge = Knowledge::new(view);
+        let start = Instant::now();
+        let mut totals = vec![0.0; legal.len()];
+        let mut deals = 0;
+
+        loop {
+            let done = match self.budget {
+                Budget::Iterations(n) => deals >= n.max(1),
+                Budget::Time(time) => deals > 0 && start.elapsed() >= time,
+            };
+            if done {
+                break;
+            }
+
+            let hands = knowledge.sample(&mut self.rng).map(|_, hand| {
+                Pile::from_vec(
+                    hand.iter()
+                        .map(|c| Card::from_description(c).expect("Sampled from tysiac cards"))
+                        .collect(),
+                )
+            });
+            let state = SomeState::Playing(playing.with_hands(hands));
+            match self.evaluator {
+                Evaluator::Rollout => {
+                    for (total, card) in totals.iter_mut().zip(legal.iter()) {
+                        *total +=
+                            self.play_out(view.seat(), state.clone(), view.game().clone(), *card);
+                    }
+                }
+                Evaluator::DoubleDummy => {
+                    let (bid_winner, _) = state.bid().expect("Cards are played after bidding");
+                    let values = DoubleDummy::new(&state)
+                        .expect("The sampled state is playing")
+                        .card_values(Side::of(view.seat(), bid_winner));
+                    for (total, card) in totals.iter_mut().zip(legal.iter()) {
+                        let (_, value) = values
+                            .iter()
+                            .find(|(c, _)| c == card)
+                            .expect("Both agree on the legal cards");
+                        *total += *value as f64;
+                    }
+                }
+            }
+            deals += 1;
+        }
+
+        legal
+            .into_iter()
+            .zip(totals)
+            .map(|(card, total)| (card, total / deals as f64))
+            .collect()
+    }
+
+    /// Plays `card` and then the rest of the hand with the rollout
+    /// heuristics, scoring the points taken by `seat`'s side: the bid
+    /// winner's score, or what the two defenders gained between them.
+    fn play_out(
+        &self,
+        seat: Player,
+        state: SomeState,
+        mut game: Game,
+        card: card_games_lib::Card,
+    ) -> f64 {
+        let (bid_winner, _) = state.bid().expect("Cards are played after bidding");
+        let before = Seats::new(|player| game.points(player));
+        let mut input = StateInput::Playing(card);
+
+        let mut state = state;
+        loop {
+            state = state.step(&mut game, input).0;
+            if state.state() != State::Playing {
+                break;
+            }
+
+            let view = View::new(state.next_player(), &state, &game);
+            let legal: Vec<_> = view.legal_cards().iter().collect();
+            let card = self
+                .rollout
+                .card_to_play(&view, &legal)
+                .expect("The player to move has a legal card");
+            input = StateInput::Playing(card);
+        }
+
+        let score = match Side::of(seat, bid_winner) {
+            Side::Bidder => state.bidder_score().expect("The hand is finished"),
+            Side::Defenders => Player::all()
+                .filter(|player| *player != bid_winner)
+                .map(|player| game.points(player) - before[player])
+                .sum(),
+        };
+        score as f64
+    }
+}
+
+impl<R> Agent for MonteCarloAgent<R>
+where
+    R: Rng + SeedableRng,
+{
+    fn act(&mut self, view: &View<'_>) -> StateInput {
+        if view.state() != State::Playing {
+            return self.fallback.act(view);
+        }
+
+        let best = self
+            .evaluate(view)
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).expect("Scores are finite"))
+            .map(|(card, _)| card);
+        StateInput::Playing(best.expect("The player to move has a legal card"))
+    }
+}
@@ -0,0 +1,971 @@
+//! The messages between a tysiac server and its clients.
+//!
+//! A connection starts in the `Text` encoding, one message per line such as
+//! `join lunch Ada` or `play QH`. A client may send `hello <version>
+//! <encoding>` at any time; if the server speaks that version it answers
+//! `welcome` in the old encoding and both sides use the new one from the
+//! next message. Otherwise it answers with a `version` error naming the
+//! version it speaks.
+//!
+//! `Json` frames are one object per line, with the message kind under
+//! `"type"`. `Binary` frames are a big-endian `u32` length then the
+//! payload: a tag byte and the fields in order. Numbers are LEB128, signed
+//! ones zigzagged first; strings are a length then UTF-8; options are a
+//! `0` or `1` byte then the value; cards are `4 * rank + suit` with ranks
+//! from ace to king and suits diamonds, clubs, hearts, spades.
+//!
+//! Names in the text form of `TableInfo` are percent-encoded, leaving only
+//! letters, digits and `-_.'~` as they are, and escaping a name of just `-`.
+//!
+//! `conformance.txt` next to this file holds every message in all three
+//! encodings, for checking other clients against.
+
+use crate::agent::View;
+use crate::terminal::describe_error;
+use card_games_lib::{Card, Seat, Suit};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::str::FromStr;
+use tysiac::{Fives, Player, State, StateError, StateInput};
+
+pub mod binary;
+mod json;
+
+/// Bumped whenever a message changes in any encoding.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+/// Frames with a longer payload are refused rather than read.
+pub const MAX_FRAME: usize = 1 << 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Text,
+    Json,
+    Binary,
+}
+
+/// What a client sends.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ClientMessage {
+    /// Ask for `version` of the protocol, switching to `encoding`.
+    Hello { version: u16, encoding: Encoding },
+    /// Take the first free seat at an open table.
+    Join { table: String, name: String },
+    /// Take back a seat after losing the connection, with the token given
+    /// when the seat was taken.
+    Rejoin { table: String, token: String },
+    /// Open a table and take its first seat.
+    Create {
+        table: String,
+        preset: Preset,
+        name: String,
+    },
+    /// Ask for `Tables`.
+    List,
+    /// Take a seat at the fullest open table playing `preset`, opening one
+    /// if none has room.
+    Find { preset: Preset, name: String },
+    /// Watch a table without a seat or a hand.
+    Spectate { table: String },
+    /// Agree to start once every seat is taken.
+    Ready,
+    /// Seat bots in every empty seat.
+    #[serde(rename = "bots")]
+    FillBots,
+    /// Give up a seat, or stop watching. A seat given up during a match
+    /// goes to a bot.
+    Leave,
+    /// Chat to the table.
+    #[serde(with = "json::text")]
+    Say(String),
+    /// Tagged by the move, e.g. `bid` or `play`.
+    #[serde(untagged, with = "json::input")]
+    Move(StateInput),
+}
+
+/// What the server sends.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ServerMessage {
+    Welcome {
+        version: u16,
+        encoding: Encoding,
+    },
+    Seated {
+        table: String,
+        #[serde(with = "json::named")]
+        seat: Player,
+        token: String,
+    },
+    Joined {
+        #[serde(with = "json::named")]
+        seat: Player,
+        name: String,
+    },
+    Left {
+        #[serde(with = "json::named")]
+        seat: Player,
+    },
+    Disconnected {
+        #[serde(with = "json::named")]
+        seat: Player,
+    },
+    Reconnected {
+        #[serde(with = "json::named")]
+        seat: Player,
+    },
+    Chat {
+        #[serde(with = "json::named")]
+        seat: Player,
+        text: String,
+    },
+    /// Something that happened at the table, e.g. `B plays Q♥`.
+    #[serde(with = "json::text")]
+    Event(String),
+    /// The open tables, sorted by name.
+    #[serde(with = "json::tables")]
+    Tables(Vec<TableInfo>),
+    /// A table changed before or after a match.
+    #[serde(with = "json::table")]
+    Table(TableInfo),
+    Error {
+        #[serde(flatten)]
+        kind: ErrorKind,
+        message: String,
+    },
+    State(Snapshot),
+}
+
+/// Table settings a client can pick from. The game always plays to 1000;
+/// presets change what the lobby adds around it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Standard,
+    /// Five hands, the highest score winning.
+    Quick,
+    /// Easy bots.
+    Casual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub name: String,
+    pub preset: Preset,
+    pub started: bool,
+    pub spectators: usize,
+    pub seats: [SeatInfo; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SeatInfo {
+    Empty,
+    Human { name: String, ready: bool },
+    Bot,
+}
+
+/// Why the server refused a message, for clients to act on without
+/// reading the message text.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "kebab-case")]
+pub enum ErrorKind {
+    /// Anything not about the game, e.g. joining a full table.
+    Request,
+    /// `Hello` asked for a version other than `supported`.
+    Version {
+        supported: u16,
+    },
+    IncorrectPlayer {
+        #[serde(with = "json::named")]
+        current: Player,
+        #[serde(with = "json::optional")]
+        attempted: Option<Player>,
+    },
+    /// The game refused the move with a `StateError` raised in this state.
+    #[serde(with = "json::rejected")]
+    Rejected(State),
+    /// A move for `given` while the game is in `held`.
+    WrongState {
+        #[serde(with = "json::named")]
+        held: State,
+        #[serde(with = "json::named")]
+        given: State,
+    },
+    NoState,
+}
+
+/// The table as one seat sees it. Other hands are only counted, and
+/// spectators, who have no seat, see no hand at all.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    #[serde(with = "json::optional")]
+    pub seat: Option<Player>,
+    #[serde(with = "json::named")]
+    pub state: State,
+    #[serde(with = "json::named")]
+    pub to_move: Player,
+    pub scores: [isize; 3],
+    #[serde(with = "json::bid")]
+    pub bid: Option<(Player, usize)>,
+    #[serde(with = "json::optional")]
+    pub trump: Option<Suit>,
+    #[serde(with = "json::trick")]
+    pub trick: Vec<(Player, Card)>,
+    #[serde(with = "json::all")]
+    pub hand: Vec<Card>,
+    pub hand_sizes: [usize; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError(pub String);
+
+/// A message that can be sent in every `Encoding`. The payload excludes
+/// the framing, which `write_frame` and `read_frame` add and remove.
+pub trait Wire: Sized {
+    fn encode(&self, encoding: Encoding) -> Vec<u8>;
+    fn decode(encoding: Encoding, payload: &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// The states in the order the binary encoding numbers them.
+pub(crate) const STATES: [State; 6] = [
+    State::Bidding,
+    State::AdjustingBid,
+    State::Distrubuting,
+    State::Playing,
+    State::Finished,
+    State::GameOver,
+];
+
+impl Encoding {
+    pub const ALL: [Encoding; 3] = [Encoding::Text, Encoding::Json, Encoding::Binary];
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Standard, Preset::Quick, Preset::Casual];
+}
+
+impl ErrorKind {
+    pub fn of(error: &crate::Error) -> ErrorKind {
+        match error {
+            crate::Error::Game(card_games_lib::Error::StepError(error)) => {
+                ErrorKind::Rejected(rejected_in(error))
+            }
+            crate::Error::Game(card_games_lib::Error::NotInCorrectStateError { held, given }) => {
+                ErrorKind::WrongState {
+                    held: *held,
+                    given: *given,
+                }
+            }
+            crate::Error::IncorrectPlayer { current, attempted } => ErrorKind::IncorrectPlayer {
+                current: *current,
+                attempted: *attempted,
+            },
+            crate::Error::NoState => ErrorKind::NoState,
+        }
+    }
+}
+
+impl ServerMessage {
+    /// `error` as the player who caused it is told about it.
+    pub fn error(error: &crate::Error) -> ServerMessage {
+        ServerMessage::Error {
+            kind: ErrorKind::of(error),
+            message: describe_error(error),
+        }
+    }
+
+    pub fn request_error(message: &str) -> ServerMessage {
+        ServerMessage::Error {
+            kind: ErrorKind::Request,
+            message: message.to_owned(),
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn new(view: &View<'_>) -> Snapshot {
+        let [a, b, c] = [Player::A, Player::B, Player::C];
+
+        Snapshot {
+            seat: Some(view.seat()),
+            state: view.state(),
+            to_move: view.to_move(),
+            scores: [view.points(a), view.points(b), view.points(c)],
+            bid: view.bid(),
+            trump: view.trump().map(tysiac::Suit::description),
+            trick: view.trick().map_or(vec![], |trick| {
+                trick
+                    .plays()
+                    .map(|(player, card)| (*player, card.description()))
+                    .collect()
+            }),
+            hand: view.hand().map_or(vec![], |hand| {
+                hand.iter().map(tysiac::Card::description).collect()
+            }),
+            hand_sizes: [view.hand_len(a), view.hand_len(b), view.hand_len(c)],
+        }
+    }
+
+    /// `view` without its seat or hand.
+    pub fn spectator(view: &View<'_>) -> Snapshot {
+        Snapshot {
+            seat: None,
+            hand: vec![],
+            ..Snapshot::new(view)
+        }
+    }
+}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientMessage::Hello { version, encoding } => {
+                write!(f, "hello {} {}", version, encoding)
+            }
+            ClientMessage::Join { table, name } => write!(f, "join {} {}", table, name),
+            ClientMessage::Rejoin { table, token } => write!(f, "rejoin {} {}", table, token),
+            ClientMessage::Create {
+                table,
+                preset,
+                name,
+            } => write!(f, "create {} {} {}", table, preset, name),
+            ClientMessage::List => write!(f, "list"),
+            ClientMessage::Find { preset, name } => write!(f, "find {} {}", preset, name),
+            ClientMessage::Spectate { table } => write!(f, "spectate {}", table),
+            ClientMessage::Ready => write!(f, "ready"),
+            ClientMessage::FillBots => write!(f, "bots"),
+            ClientMessage::Leave => write!(f, "leave"),
+            ClientMessage::Say(text) => write!(f, "say {}", text),
+            ClientMessage::Move(StateInput::Bidding(None)) => write!(f, "bid pass"),
+            ClientMessage::Move(StateInput::Bidding(Some(raise))) => {
+                write!(f, "bid {}", usize::from(*raise))
+            }
+            ClientMessage::Move(StateInput::AdjustingBid(raise)) => {
+                write!(f, "adjust {}", usize::from(*raise))
+            }
+            ClientMessage::Move(StateInput::Concede()) => write!(f, "concede"),
+            ClientMessage::Move(StateInput::Distrubuting(next, prev)) => {
+                write!(f, "pass {} {}", next, prev)
+            }
+            ClientMessage::Move(StateInput::Playing(card)) => write!(f, "play {}", card),
+            ClientMessage::Move(StateInput::Finished(seed)) => write!(f, "deal {}", seed),
+            ClientMessage::Move(StateInput::GameOver()) => write!(f, "over"),
+        }
+    }
+}
+
+impl FromStr for ClientMessage {
+    type Err = DecodeError;
+
+    fn from_str(line: &str) -> Result<ClientMessage, DecodeError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let message = match words.as_slice() {
+            ["say", ..] => ClientMessage::Say(rest(line).to_owned()),
+            ["hello", version, encoding] => ClientMessage::Hello {
+                version: number(version)?,
+                encoding: encoding.parse()?,
+            },
+            ["join", table, name @ ..] if !name.is_empty() => ClientMessage::Join {
+                table: table.to_string(),
+                name: name.join(" "),
+            },
+            ["rejoin", table, token] => ClientMessage::Rejoin {
+                table: table.to_string(),
+                token: token.to_string(),
+            },
+            ["create", table, preset, name @ ..] if !name.is_empty() => ClientMessage::Create {
+                table: table.to_string(),
+                preset: preset.parse()?,
+                name: name.join(" "),
+            },
+            ["list"] => ClientMessage::List,
+            ["find", preset, name @ ..] if !name.is_empty() => ClientMessage::Find {
+                preset: preset.parse()?,
+                name: name.join(" "),
+            },
+            ["spectate", table] => ClientMessage::Spectate {
+                table: table.to_string(),
+            },
+            ["ready"] => ClientMessage::Ready,
+            ["bots"] => ClientMessage::FillBots,
+            ["leave"] => ClientMessage::Leave,
+            ["bid", "pass"] => ClientMessage::Move(StateInput::Bidding(None)),
+            ["bid", raise] => ClientMessage::Move(StateInput::Bidding(Some(fives(raise)?))),
+            ["adjust", raise] => ClientMessage::Move(StateInput::AdjustingBid(fives(raise)?)),
+            ["concede"] => ClientMessage::Move(StateInput::Concede()),
+            ["pass", next, prev] => {
+                ClientMessage::Move(StateInput::Distrubuting(card(next)?, card(prev)?))
+            }
+            ["play", played] => ClientMessage::Move(StateInput::Playing(card(played)?)),
+            ["deal", seed] => ClientMessage::Move(StateInput::Finished(number(seed)?)),
+            ["over"] => ClientMessage::Move(StateInput::GameOver()),
+            _ => return Err(DecodeError(format!("Unknown message {:?}", line))),
+        };
+        Ok(message)
+    }
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerMessage::Welcome { version, encoding } => {
+                write!(f, "welcome {} {}", version, encoding)
+            }
+            ServerMessage::Seated { table, seat, token } => {
+                write!(f, "seated {} {:?} {}", table, seat, token)
+            }
+            ServerMessage::Joined { seat, name } => write!(f, "joined {:?} {}", seat, name),
+            ServerMessage::Left { seat } => write!(f, "left {:?}", seat),
+            ServerMessage::Disconnected { seat } => write!(f, "disconnected {:?}", seat),
+            ServerMessage::Reconnected { seat } => write!(f, "reconnected {:?}", seat),
+            ServerMessage::Chat { seat, text } => write!(f, "chat {:?} {}", seat, text),
+            ServerMessage::Event(text) => write!(f, "event {}", text),
+            ServerMessage::Tables(tables) => {
+                write!(f, "tables")?;
+                for table in tables {
+                    write!(f, " {}", table)?;
+                }
+                Ok(())
+            }
+            ServerMessage::Table(table) => write!(f, "table {}", table),
+            ServerMessage::Error { kind, message } => write!(f, "error {} {}", kind, message),
+            ServerMessage::State(snapshot) => write!(f, "state {}", snapshot),
+        }
+    }
+}
+
+impl FromStr for ServerMessage {
+    type Err = DecodeError;
+
+    fn from_str(line: &str) -> Result<ServerMessage, DecodeError> {
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let message = match (kind, words.as_slice()) {
+            ("welcome", [version, encoding]) => ServerMessage::Welcome {
+                version: number(version)?,
+                encoding: encoding.parse()?,
+            },
+            ("seated", [table, seat, token]) => ServerMessage::Seated {
+                table: table.to_string(),
+                seat: player(seat)?,
+                token: token.to_string(),
+            },
+            ("joined", [seat, name @ ..]) if !name.is_empty() => ServerMessage::Joined {
+                seat: player(seat)?,
+                name: name.join(" "),
+            },
+            ("left", [seat]) => ServerMessage::Left {
+                seat: player(seat)?,
+            },
+            ("disconnected", [seat]) => ServerMessage::Disconnected {
+                seat: player(seat)?,
+            },
+            ("reconnected", [seat]) => ServerMessage::Reconnected {
+                seat: player(seat)?,
+            },
+            ("chat", [seat, ..]) => ServerMessage::Chat {
+                seat: player(seat)?,
+                text: self::rest(rest).to_owned(),
+            },
+            ("event", _) => ServerMessage::Event(rest.to_owned()),
+            ("tables", words) if words.len() % 5 == 0 => ServerMessage::Tables(
+                words
+                    .chunks(5)
+                    .map(|words| words.join(" ").parse())
+                    .collect::<Result<_, _>>()?,
+            ),
+            ("table", [_, _, _, _, _]) => ServerMessage::Table(rest.parse()?),
+            ("error", [code, ..]) => ServerMessage::Error {
+                kind: code.parse()?,
+                message: self::rest(rest).to_owned(),
+            },
+            ("state", _) => ServerMessage::State(rest.parse()?),
+            _ => return Err(DecodeError(format!("Unknown message {:?}", line))),
+        };
+        Ok(message)
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Text => write!(f, "text"),
+            Encoding::Json => write!(f, "json"),
+            Encoding::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = DecodeError;
+
+    fn from_str(text: &str) -> Result<Encoding, DecodeError> {
+        Encoding::ALL
+            .iter()
+            .copied()
+            .find(|encoding| encoding.to_string() == text)
+            .ok_or_else(|| DecodeError(format!("{:?} is not an encoding", text)))
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Preset::Standard => write!(f, "standard"),
+            Preset::Quick => write!(f, "quick"),
+            Preset::Casual => write!(f, "casual"),
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = DecodeError;
+
+    fn from_str(text: &str) -> Result<Preset, DecodeError> {
+        Preset::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.to_string() == text)
+            .ok_or_else(|| DecodeError(format!("{:?} is not a preset", text)))
+    }
+}
+
+/// `lunch quick waiting 1 Ada*,-,@`: the name, the preset, `waiting` or
+/// `playing`, the number of spectators, then the seats. A seat is `-` when
+/// empty, `@` for a bot, or the player's name with `*` once ready.
+impl fmt::Display for TableInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seats: Vec<String> = self
+            .seats
+            .iter()
+            .map(|seat| match seat {
+                SeatInfo::Empty => "-".to_owned(),
+                SeatInfo::Bot => "@".to_owned(),
+                SeatInfo::Human { name, ready } => {
+                    format!("{}{}", escape(name), if *ready { "*" } else { "" })
+                }
+            })
+            .collect();
+        write!(
+            f,
+            "{} {} {} {} {}",
+            escape(&self.name),
+            self.preset,
+            if self.started { "playing" } else { "waiting" },
+            self.spectators,
+            seats.join(",")
+        )
+    }
+}
+
+impl FromStr for TableInfo {
+    type Err = DecodeError;
+
+    fn from_str(text: &str) -> Result<TableInfo, DecodeError> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let (name, preset, started, spectators, seats) = match words.as_slice() {
+            [name, preset, started, spectators, seats] => {
+                (name, preset, started, spectators, seats)
+            }
+            _ => return Err(DecodeError(format!("Expected a table, got {:?}", text))),
+        };
+        let seats: Vec<SeatInfo> = seats
+            .split(',')
+            .map(|seat| {
+                Ok(match seat {
+                    "-" => SeatInfo::Empty,
+                    "@" => SeatInfo::Bot,
+                    seat => match seat.strip_suffix('*') {
+                        Some(name) => SeatInfo::Human {
+                            name: unescape(name)?,
+                            ready: true,
+                        },
+                        None => SeatInfo::Human {
+                            name: unescape(seat)?,
+                            ready: false,
+                        },
+                    },
+                })
+            })
+            .collect::<Result<_, DecodeError>>()?;
+        let seats = match <[SeatInfo; 3]>::try_from(seats) {
+            Ok(seats) => seats,
+            Err(_) => return Err(DecodeError("A table needs three seats".to_owned())),
+        };
+
+        Ok(TableInfo {
+            name: unescape(name)?,
+            preset: preset.parse()?,
+            started: match *started {
+                "waiting" => false,
+                "playing" => true,
+                _ => {
+                    return Err(DecodeError(format!(
+                        "{:?} is not waiting or playing",
+                        started
+                    )))
+                }
+            },
+            spectators: number(spectators)?,
+            seats,
+        })
+    }
+}
+
+/// `request`, `version:1`, `incorrect-player:B:A` (`-` when the attempt
+/// had no player), `rejected:Playing`, `wrong-state:Bidding:Playing` or
+/// `no-state`.
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Request => write!(f, "request"),
+            ErrorKind::Version { supported } => write!(f, "version:{}", supported),
+            ErrorKind::IncorrectPlayer { current, attempted } => {
+                write!(f, "incorrect-player:{:?}:", current)?;
+                match attempted {
+                    Some(attempted) => write!(f, "{:?}", attempted),
+                    None => write!(f, "-"),
+                }
+            }
+            ErrorKind::Rejected(state) => write!(f, "rejected:{:?}", state),
+            ErrorKind::WrongState { held, given } => {
+                write!(f, "wrong-state:{:?}:{:?}", held, given)
+            }
+            ErrorKind::NoState => write!(f, "no-state"),
+        }
+    }
+}
+
+impl FromStr for ErrorKind {
+    type Err = DecodeError;
+
+    fn from_str(text: &str) -> Result<ErrorKind, DecodeError> {
+        let parts: Vec<&str> = text.split(':').collect();
+        Ok(match parts.as_slice() {
+            ["request"] => ErrorKind::Request,
+            ["version", supported] => ErrorKind::Version {
+                supported: number(supported)?,
+            },
+            ["incorrect-player", current, attempted] => ErrorKind::IncorrectPlayer {
+                current: player(current)?,
+                attempted: match *attempted {
+                    "-" => None,
+                    attempted => Some(player(attempted)?),
+                },
+            },
+            ["rejected", held] => ErrorKind::Rejected(state(held)?),
+            ["wrong-state", held, given] => ErrorKind::WrongState {
+                held: state(held)?,
+                given: state(given)?,
+            },
+            ["no-state"] => ErrorKind::NoState,
+            _ => return Err(DecodeError(format!("{:?} is not an error kind", text))),
+        })
+    }
+}
+
+/// `Playing seat=B move=A scores=0,0,0 bid=A:100 trump=H trick=A:QH,B:9H
+/// hand=AS,QS hands=8,7,8`, with `-` for no seat, bid or trump.
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |items: Vec<String>| items.join(",");
+        write!(f, "{:?} ", self.state)?;
+        match self.seat {
+            Some(seat) => write!(f, "seat={:?} ", seat)?,
+            None => write!(f, "seat=- ")?,
+        }
+        write!(
+            f,
+            "move={:?} scores={} ",
+            self.to_move,
+            join(self.scores.iter().map(ToString::to_string).collect())
+        )?;
+        match self.bid {
+            Some((player, bid)) => write!(f, "bid={:?}:{} ", player, bid)?,
+            None => write!(f, "bid=- ")?,
+        }
+        match self.trump {
+            Some(trump) => write!(f, "trump={} ", trump)?,
+            None => write!(f, "trump=- ")?,
+        }
+        write!(
+            f,
+            "trick={} hand={} hands={}",
+            join(
+                self.trick
+                    .iter()
+                    .map(|(player, card)| format!("{:?}:{}", player, card))
+                    .collect()
+            ),
+            join(self.hand.iter().map(ToString::to_string).collect()),
+            join(self.hand_sizes.iter().map(ToString::to_string).collect())
+        )
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = DecodeError;
+
+    fn from_str(text: &str) -> Result<Snapshot, DecodeError> {
+        let mut words = text.split_whitespace();
+        let state = state(words.next().unwrap_or(""))?;
+        let mut fields = std::collections::HashMap::new();
+        for word in words {
+            let (key, value) = word
+                .split_once('=')
+                .ok_or_else(|| DecodeError(format!("Expected a field, got {:?}", word)))?;
+            fields.insert(key, value);
+        }
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .copied()
+                .ok_or_else(|| DecodeError(format!("Missing field {:?}", key)))
+        };
+        let list = |key: &str| -> Result<Vec<&str>, DecodeError> {
+            Ok(field(key)?.split(',').filter(|s| !s.is_empty()).collect())
+        };
+        let three = |key: &str| -> Result<[&str; 3], DecodeError> {
+            match list(key)?.as_slice() {
+                [a, b, c] => Ok([*a, *b, *c]),
+                _ => Err(DecodeError(format!("{:?} needs three values", key))),
+            }
+        };
+        let scores = three("scores")?;
+        let hand_sizes = three("hands")?;
+
+        Ok(Snapshot {
+            seat: match field("seat")? {
+                "-" => None,
+                seat => Some(player(seat)?),
+            },
+            state,
+            to_move: player(field("move")?)?,
+            scores: [number(scores[0])?, number(scores[1])?, number(scores[2])?],
+            bid: match field("bid")? {
+                "-" => None,
+                bid => {
+                    let (bidder, bid) = bid
+                        .split_once(':')
+                        .ok_or_else(|| DecodeError(format!("Expected a bid, got {:?}", bid)))?;
+                    Some((player(bidder)?, number(bid)?))
+                }
+            },
+            trump: match field("trump")? {
+                "-" => None,
+                trump => Some(
+                    trump
+                        .parse()
+                        .map_err(|_| DecodeError(format!("{:?} is not a suit", trump)))?,
+                ),
+            },
+            trick: list("trick")?
+                .into_iter()
+                .map(|play| {
+                    let (seat, played) = play
+                        .split_once(':')
+                        .ok_or_else(|| DecodeError(format!("Expected a play, got {:?}", play)))?;
+                    Ok((player(seat)?, card(played)?))
+                })
+                .collect::<Result<_, DecodeError>>()?,
+            hand: list("hand")?
+                .into_iter()
+                .map(card)
+                .collect::<Result<_, _>>()?,
+            hand_sizes: [
+                number(hand_sizes[0])?,
+                number(hand_sizes[1])?,
+                number(hand_sizes[2])?,
+            ],
+        })
+    }
+}
+
+impl Wire for ClientMessage {
+    fn encode(&self, encoding: Encoding) -> Vec<u8> {
+        match encoding {
+            Encoding::Text => self.to_string().into_bytes(),
+            Encoding::Json => json(self),
+            Encoding::Binary => binary::from_client(self),
+        }
+    }
+
+    fn decode(encoding: Encoding, payload: &[u8]) -> Result<ClientMessage, DecodeError> {
+        match encoding {
+            Encoding::Text => utf8(payload)?.parse(),
+            Encoding::Json => json::client(utf8(payload)?),
+            Encoding::Binary => binary::to_client(payload),
+        }
+    }
+}
+
+impl Wire for ServerMessage {
+    fn encode(&self, encoding: Encoding) -> Vec<u8> {
+        match encoding {
+            Encoding::Text => self.to_string().into_bytes(),
+            Encoding::Json => json(self),
+            Encoding::Binary => binary::from_server(self),
+        }
+    }
+
+    fn decode(encoding: Encoding, payload: &[u8]) -> Result<ServerMessage, DecodeError> {
+        match encoding {
+            Encoding::Text => utf8(payload)?.parse(),
+            Encoding::Json => {
+                serde_json::from_str(utf8(payload)?).map_err(|e| DecodeError(e.to_string()))
+            }
+            Encoding::Binary => binary::to_server(payload),
+        }
+    }
+}
+
+pub fn write_frame<W: Write>(writer: &mut W, encoding: Encoding, payload: &[u8]) -> io::Result<()> {
+    match encoding {
+        Encoding::Text | Encoding::Json => {
+            writer.write_all(payload)?;
+            writer.write_all(b"\n")?;
+        }
+        Encoding::Binary => {
+            writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+            writer.write_all(payload)?;
+        }
+    }
+    writer.flush()
+}
+
+/// The next payload, or `None` once the other side has hung up.
+pub fn read_frame<R: BufRead>(reader: &mut R, encoding: Encoding) -> io::Result<Option<Vec<u8>>> {
+    match encoding {
+        Encoding::Text | Encoding::Json => {
+            let mut line = Vec::new();
+            // Room for the payload and a "\r\n"
+            let mut limited = reader.take(MAX_FRAME as u64 + 2);
+            if limited.read_until(b'\n', &mut line)? == 0 {
+                return Ok(None);
+            }
+            while matches!(line.last(), Some(b'\n') | Some(b'\r')) {
+                line.pop();
+            }
+            if line.len() > MAX_FRAME {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("A line longer than {} bytes is too long", MAX_FRAME),
+                ));
+            }
+            Ok(Some(line))
+        }
+        Encoding::Binary => {
+            let mut length = [0; 4];
+            match reader.read_exact(&mut length) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                result => result?,
+            }
+            let length = u32::from_be_bytes(length) as usize;
+            if length > MAX_FRAME {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("A frame of {} bytes is too long", length),
+                ));
+            }
+            let mut payload = vec![0; length];
+            reader.read_exact(&mut payload)?;
+            Ok(Some(payload))
+        }
+    }
+}
+
+fn rejected_in(error: &StateError) -> State {
+    match error {
+        StateError::Bidding(_) => State::Bidding,
+        StateError::AdjustingBid(_) | StateError::Concede(_) => State::AdjustingBid,
+        StateError::Distrubuting(_) => State::Distrubuting,
+        StateError::Playing(_) => State::Playing,
+        StateError::Finished(_) => State::Finished,
+        StateError::GameOver(_) => State::GameOver,
+    }
+}
+
+/// Everything after the first word, as written.
+fn rest(line: &str) -> &str {
+    line.trim_start()
+        .split_once(' ')
+        .map_or("", |(_, rest)| rest)
+}
+
+fn escape(name: &str) -> String {
+    let mut escaped = String::new();
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.'~".contains(&byte) {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    // Not to be read as an empty seat
+    if escaped == "-" {
+        escaped = "%2D".to_owned();
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> Result<String, DecodeError> {
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| DecodeError(format!("Bad escape in {:?}", text)))?;
+            bytes.push(hex);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| DecodeError(format!("{:?} is not UTF-8", text)))
+}
+
+fn json<T: Serialize>(message: &T) -> Vec<u8> {
+    serde_json::to_vec(message).expect("Every message has a JSON form")
+}
+
+fn utf8(payload: &[u8]) -> Result<&str, DecodeError> {
+    std::str::from_utf8(payload).map_err(|_| DecodeError("The message is not UTF-8".to_owned()))
+}
+
+pub(crate) fn player(text: &str) -> Result<Player, DecodeError> {
+    Player::all()
+        .find(|player| format!("{:?}", player) == text)
+        .ok_or_else(|| DecodeError(format!("{:?} is not a seat", text)))
+}
+
+pub(crate) fn state(text: &str) -> Result<State, DecodeError> {
+    STATES
+        .iter()
+        .copied()
+        .find(|state| format!("{:?}", state) == text)
+        .ok_or_else(|| DecodeError(format!("{:?} is not a state", text)))
+}
+
+pub(crate) fn card(text: &str) -> Result<Card, DecodeError> {
+    text.parse()
+        .map_err(|_| DecodeError(format!("{:?} is not a card", text)))
+}
+
+pub(crate) fn number<T: FromStr>(text: &str) -> Result<T, DecodeError> {
+    text.parse()
+        .map_err(|_| DecodeError(format!("{:?} is not a number", text)))
+}
+
+pub(crate) fn fives(text: &str) -> Result<Fives, DecodeError> {
+    Fives::new(number(text)?)
+        .ok_or_else(|| DecodeError(format!("{} is not a multiple of five", text)))
+}
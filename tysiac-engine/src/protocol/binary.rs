@@ -0,0 +1,531 @@
+use super::{
+    ClientMessage, DecodeError, Encoding, ErrorKind, Preset, SeatInfo, ServerMessage, Snapshot,
+    TableInfo, STATES,
+};
+use card_games_lib::{Card, Rank, Seat, Suit};
+use std::convert::TryFrom;
+use tysiac::{Fives, Player, State, StateInput};
+
+const HELLO: u8 = 0x00;
+const JOIN: u8 = 0x01;
+const REJOIN: u8 = 0x02;
+const LEAVE: u8 = 0x03;
+const SAY: u8 = 0x04;
+const CREATE: u8 = 0x05;
+const LIST: u8 = 0x06;
+const FIND: u8 = 0x07;
+const SPECTATE: u8 = 0x08;
+const READY: u8 = 0x09;
+const BOTS: u8 = 0x0a;
+const BID: u8 = 0x10;
+const ADJUST: u8 = 0x11;
+const CONCEDE: u8 = 0x12;
+const PASS: u8 = 0x13;
+const PLAY: u8 = 0x14;
+const DEAL: u8 = 0x15;
+const OVER: u8 = 0x16;
+
+const WELCOME: u8 = 0x80;
+const SEATED: u8 = 0x81;
+const JOINED: u8 = 0x82;
+const LEFT: u8 = 0x83;
+const DISCONNECTED: u8 = 0x84;
+const RECONNECTED: u8 = 0x85;
+const CHAT: u8 = 0x86;
+const EVENT: u8 = 0x87;
+const ERROR: u8 = 0x88;
+const STATE: u8 = 0x89;
+const TABLES: u8 = 0x8a;
+const TABLE: u8 = 0x8b;
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn new(tag: u8) -> Writer {
+        Writer(vec![tag])
+    }
+
+    fn byte(&mut self, byte: u8) -> &mut Writer {
+        self.0.push(byte);
+        self
+    }
+
+    fn unsigned(&mut self, mut n: u64) -> &mut Writer {
+        while n >= 0x80 {
+            self.0.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.byte(n as u8)
+    }
+
+    fn signed(&mut self, n: i64) -> &mut Writer {
+        self.unsigned(((n << 1) ^ (n >> 63)) as u64)
+    }
+
+    fn string(&mut self, s: &str) -> &mut Writer {
+        self.unsigned(s.len() as u64);
+        self.0.extend_from_slice(s.as_bytes());
+        self
+    }
+
+    fn player(&mut self, player: Player) -> &mut Writer {
+        self.byte(player.index() as u8)
+    }
+
+    fn state(&mut self, state: State) -> &mut Writer {
+        let index = STATES.iter().position(|s| *s == state);
+        self.byte(index.expect("STATES holds every state") as u8)
+    }
+
+    fn card(&mut self, card: Card) -> &mut Writer {
+        self.byte((card.rank().index() * 4 + card.suit().index()) as u8)
+    }
+
+    fn encoding(&mut self, encoding: Encoding) -> &mut Writer {
+        let index = Encoding::ALL.iter().position(|e| *e == encoding);
+        self.byte(index.expect("ALL holds every encoding") as u8)
+    }
+
+    fn preset(&mut self, preset: Preset) -> &mut Writer {
+        let index = Preset::ALL.iter().position(|p| *p == preset);
+        self.byte(index.expect("ALL holds every preset") as u8)
+    }
+
+    fn table(&mut self, info: &TableInfo) -> &mut Writer {
+        self.string(&info.name)
+            .preset(info.preset)
+            .byte(info.started as u8)
+            .unsigned(info.spectators as u64);
+        for seat in info.seats.iter() {
+            match seat {
+                SeatInfo::Empty => self.byte(0),
+                SeatInfo::Human { name, ready } => self.byte(1).string(name).byte(*ready as u8),
+                SeatInfo::Bot => self.byte(2),
+            };
+        }
+        self
+    }
+
+    fn optional<T>(&mut self, value: Option<T>, f: impl FnOnce(&mut Writer, T)) -> &mut Writer {
+        match value {
+            Some(value) => {
+                self.byte(1);
+                f(self, value);
+                self
+            }
+            None => self.byte(0),
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.0)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: &str) -> DecodeError {
+        DecodeError(format!("{} at byte {}", message, self.at))
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.at)
+            .ok_or_else(|| self.error("The message ends early"))?;
+        self.at += 1;
+        Ok(byte)
+    }
+
+    fn unsigned(&mut self) -> Result<u64, DecodeError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(self.error("A number is too long"))
+    }
+
+    fn number<T: TryFrom<u64>>(&mut self) -> Result<T, DecodeError> {
+        let n = self.unsigned()?;
+        T::try_from(n).map_err(|_| self.error("A number is out of range"))
+    }
+
+    fn signed(&mut self) -> Result<i64, DecodeError> {
+        let n = self.unsigned()?;
+        Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let length: usize = self.number()?;
+        let end = self
+            .at
+            .checked_add(length)
+            .ok_or_else(|| self.error("A string is too long"))?;
+        let bytes = self
+            .bytes
+            .get(self.at..end)
+            .ok_or_else(|| self.error("The message ends early"))?;
+        self.at = end;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.error("A string is not UTF-8"))
+    }
+
+    fn player(&mut self) -> Result<Player, DecodeError> {
+        let index = self.byte()? as usize;
+        Player::all()
+            .find(|player| player.index() == index)
+            .ok_or_else(|| self.error("Not a seat"))
+    }
+
+    fn state(&mut self) -> Result<State, DecodeError> {
+        let index = self.byte()? as usize;
+        STATES
+            .get(index)
+            .copied()
+            .ok_or_else(|| self.error("Not a state"))
+    }
+
+    fn card(&mut self) -> Result<Card, DecodeError> {
+        let index = self.byte()? as usize;
+        match (Rank::ALL.get(index / 4), Suit::ALL.get(index % 4)) {
+            (Some(rank), Some(suit)) => Ok(Card(*rank, *suit)),
+            _ => Err(self.error("Not a card")),
+        }
+    }
+
+    fn suit(&mut self) -> Result<Suit, DecodeError> {
+        let index = self.byte()? as usize;
+        Suit::ALL
+            .get(index)
+            .copied()
+            .ok_or_else(|| self.error("Not a suit"))
+    }
+
+    fn encoding(&mut self) -> Result<Encoding, DecodeError> {
+        let index = self.byte()? as usize;
+        Encoding::ALL
+            .get(index)
+            .copied()
+            .ok_or_else(|| self.error("Not an encoding"))
+    }
+
+    fn preset(&mut self) -> Result<Preset, DecodeError> {
+        let index = self.byte()? as usize;
+        Preset::ALL
+            .get(index)
+            .copied()
+            .ok_or_else(|| self.error("Not a preset"))
+    }
+
+    fn flag(&mut self) -> Result<bool, DecodeError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(self.error("Expected 0 or 1")),
+        }
+    }
+
+    fn seat(&mut self) -> Result<SeatInfo, DecodeError> {
+        Ok(match self.byte()? {
+            0 => SeatInfo::Empty,
+            1 => SeatInfo::Human {
+                name: self.string()?,
+                ready: self.flag()?,
+            },
+            2 => SeatInfo::Bot,
+            _ => return Err(self.error("Not a seat")),
+        })
+    }
+
+    fn table(&mut self) -> Result<TableInfo, DecodeError> {
+        Ok(TableInfo {
+            name: self.string()?,
+            preset: self.preset()?,
+            started: self.flag()?,
+            spectators: self.number()?,
+            seats: [self.seat()?, self.seat()?, self.seat()?],
+        })
+    }
+
+    fn fives(&mut self) -> Result<Fives, DecodeError> {
+        let raise = self.number()?;
+        Fives::new(raise).ok_or_else(|| self.error("A bid is not a multiple of five"))
+    }
+
+    fn optional<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Option<T>, DecodeError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => f(self).map(Some),
+            _ => Err(self.error("Expected 0 or 1 before an optional value")),
+        }
+    }
+
+    fn list<T>(
+        &mut self,
+        mut f: impl FnMut(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Vec<T>, DecodeError> {
+        let length: usize = self.number()?;
+        if length > self.bytes.len() {
+            return Err(self.error("A list is longer than the message"));
+        }
+        (0..length).map(|_| f(self)).collect()
+    }
+
+    fn end<T>(&self, value: T) -> Result<T, DecodeError> {
+        if self.at != self.bytes.len() {
+            return Err(self.error("Expected the end of the message"));
+        }
+        Ok(value)
+    }
+}
+
+pub(super) fn from_client(message: &ClientMessage) -> Vec<u8> {
+    match message {
+        ClientMessage::Hello { version, encoding } => Writer::new(HELLO)
+            .unsigned(u64::from(*version))
+            .encoding(*encoding)
+            .finish(),
+        ClientMessage::Join { table, name } => {
+            Writer::new(JOIN).string(table).string(name).finish()
+        }
+        ClientMessage::Rejoin { table, token } => {
+            Writer::new(REJOIN).string(table).string(token).finish()
+        }
+        ClientMessage::Create {
+            table,
+            preset,
+            name,
+        } => Writer::new(CREATE)
+            .string(table)
+            .preset(*preset)
+            .string(name)
+            .finish(),
+        ClientMessage::List => Writer::new(LIST).finish(),
+        ClientMessage::Find { preset, name } => {
+            Writer::new(FIND).preset(*preset).string(name).finish()
+        }
+        ClientMessage::Spectate { table } => Writer::new(SPECTATE).string(table).finish(),
+        ClientMessage::Ready => Writer::new(READY).finish(),
+        ClientMessage::FillBots => Writer::new(BOTS).finish(),
+        ClientMessage::Leave => Writer::new(LEAVE).finish(),
+        ClientMessage::Say(text) => Writer::new(SAY).string(text).finish(),
+        ClientMessage::Move(StateInput::Bidding(raise)) => Writer::new(BID)
+            .optional(*raise, |w, raise| {
+                w.unsigned(usize::from(raise) as u64);
+            })
+            .finish(),
+        ClientMessage::Move(StateInput::AdjustingBid(raise)) => Writer::new(ADJUST)
+            .unsigned(usize::from(*raise) as u64)
+            .finish(),
+        ClientMessage::Move(StateInput::Concede()) => Writer::new(CONCEDE).finish(),
+        ClientMessage::Move(StateInput::Distrubuting(next, prev)) => {
+            Writer::new(PASS).card(*next).card(*prev).finish()
+        }
+        ClientMessage::Move(StateInput::Playing(card)) => Writer::new(PLAY).card(*card).finish(),
+        ClientMessage::Move(StateInput::Finished(seed)) => {
+            Writer::new(DEAL).unsigned(*seed).finish()
+        }
+        ClientMessage::Move(StateInput::GameOver()) => Writer::new(OVER).finish(),
+    }
+}
+
+pub(super) fn to_client(payload: &[u8]) -> Result<ClientMessage, DecodeError> {
+    let mut r = Reader {
+        bytes: payload,
+        at: 0,
+    };
+    let message = match r.byte()? {
+        HELLO => ClientMessage::Hello {
+            version: r.number()?,
+            encoding: r.encoding()?,
+        },
+        JOIN => ClientMessage::Join {
+            table: r.string()?,
+            name: r.string()?,
+        },
+        REJOIN => ClientMessage::Rejoin {
+            table: r.string()?,
+            token: r.string()?,
+        },
+        CREATE => ClientMessage::Create {
+            table: r.string()?,
+            preset: r.preset()?,
+            name: r.string()?,
+        },
+        LIST => ClientMessage::List,
+        FIND => ClientMessage::Find {
+            preset: r.preset()?,
+            name: r.string()?,
+        },
+        SPECTATE => ClientMessage::Spectate { table: r.string()? },
+        READY => ClientMessage::Ready,
+        BOTS => ClientMessage::FillBots,
+        LEAVE => ClientMessage::Leave,
+        SAY => ClientMessage::Say(r.string()?),
+        BID => ClientMessage::Move(StateInput::Bidding(r.optional(Reader::fives)?)),
+        ADJUST => ClientMessage::Move(StateInput::AdjustingBid(r.fives()?)),
+        CONCEDE => ClientMessage::Move(StateInput::Concede()),
+        PASS => ClientMessage::Move(StateInput::Distrubuting(r.card()?, r.card()?)),
+        PLAY => ClientMessage::Move(StateInput::Playing(r.card()?)),
+        DEAL => ClientMessage::Move(StateInput::Finished(r.unsigned()?)),
+        OVER => ClientMessage::Move(StateInput::GameOver()),
+        tag => return Err(DecodeError(format!("Unknown message tag {:#04x}", tag))),
+    };
+    r.end(message)
+}
+
+pub(super) fn from_server(message: &ServerMessage) -> Vec<u8> {
+    match message {
+        ServerMessage::Welcome { version, encoding } => Writer::new(WELCOME)
+            .unsigned(u64::from(*version))
+            .encoding(*encoding)
+            .finish(),
+        ServerMessage::Seated { table, seat, token } => Writer::new(SEATED)
+            .string(table)
+            .player(*seat)
+            .string(token)
+            .finish(),
+        ServerMessage::Joined { seat, name } => {
+            Writer::new(JOINED).player(*seat).string(name).finish()
+        }
+        ServerMessage::Left { seat } => Writer::new(LEFT).player(*seat).finish(),
+        ServerMessage::Disconnected { seat } => Writer::new(DISCONNECTED).player(*seat).finish(),
+        ServerMessage::Reconnected { seat } => Writer::new(RECONNECTED).player(*seat).finish(),
+        ServerMessage::Chat { seat, text } => Writer::new(CHAT).player(*seat).string(text).finish(),
+        ServerMessage::Event(text) => Writer::new(EVENT).string(text).finish(),
+        ServerMessage::Tables(tables) => {
+            let mut w = Writer::new(TABLES);
+            w.unsigned(tables.len() as u64);
+            for info in tables {
+                w.table(info);
+            }
+            w.finish()
+        }
+        ServerMessage::Table(info) => Writer::new(TABLE).table(info).finish(),
+        ServerMessage::Error { kind, message } => {
+            let mut w = Writer::new(ERROR);
+            match kind {
+                ErrorKind::Request => w.byte(0),
+                ErrorKind::Version { supported } => w.byte(1).unsigned(u64::from(*supported)),
+                ErrorKind::IncorrectPlayer { current, attempted } => w
+                    .byte(2)
+                    .player(*current)
+                    .optional(*attempted, |w, attempted| {
+                        w.player(attempted);
+                    }),
+                ErrorKind::Rejected(state) => w.byte(3).state(*state),
+                ErrorKind::WrongState { held, given } => w.byte(4).state(*held).state(*given),
+                ErrorKind::NoState => w.byte(5),
+            };
+            w.string(message).finish()
+        }
+        ServerMessage::State(snapshot) => {
+            let mut w = Writer::new(STATE);
+            w.optional(snapshot.seat, |w, seat| {
+                w.player(seat);
+            })
+            .state(snapshot.state)
+            .player(snapshot.to_move);
+            for score in snapshot.scores.iter() {
+                w.signed(*score as i64);
+            }
+            w.optional(snapshot.bid, |w, (player, bid)| {
+                w.player(player).unsigned(bid as u64);
+            })
+            .optional(snapshot.trump, |w, suit| {
+                w.byte(suit.index() as u8);
+            })
+            .unsigned(snapshot.trick.len() as u64);
+            for (player, card) in snapshot.trick.iter() {
+                w.player(*player).card(*card);
+            }
+            w.unsigned(snapshot.hand.len() as u64);
+            for card in snapshot.hand.iter() {
+                w.card(*card);
+            }
+            for size in snapshot.hand_sizes.iter() {
+                w.unsigned(*size as u64);
+            }
+            w.finish()
+        }
+    }
+}
+
+pub(super) fn to_server(payload: &[u8]) -> Result<ServerMessage, DecodeError> {
+    let mut r = Reader {
+        bytes: payload,
+        at: 0,
+    };
+    let message = match r.byte()? {
+        WELCOME => ServerMessage::Welcome {
+            version: r.number()?,
+            encoding: r.encoding()?,
+        },
+        SEATED => ServerMessage::Seated {
+            table: r.string()?,
+            seat: r.player()?,
+            token: r.string()?,
+        },
+        JOINED => ServerMessage::Joined {
+            seat: r.player()?,
+            name: r.string()?,
+        },
+        LEFT => ServerMessage::Left { seat: r.player()? },
+        DISCONNECTED => ServerMessage::Disconnected { seat: r.player()? },
+        RECONNECTED => ServerMessage::Reconnected { seat: r.player()? },
+        CHAT => ServerMessage::Chat {
+            seat: r.player()?,
+            text: r.string()?,
+        },
+        EVENT => ServerMessage::Event(r.string()?),
+        TABLES => ServerMessage::Tables(r.list(Reader::table)?),
+        TABLE => ServerMessage::Table(r.table()?),
+        ERROR => ServerMessage::Error {
+            kind: match r.byte()? {
+                0 => ErrorKind::Request,
+                1 => ErrorKind::Version {
+                    supported: r.number()?,
+                },
+                2 => ErrorKind::IncorrectPlayer {
+                    current: r.player()?,
+                    attempted: r.optional(Reader::player)?,
+                },
+                3 => ErrorKind::Rejected(r.state()?),
+                4 => ErrorKind::WrongState {
+                    held: r.state()?,
+                    given: r.state()?,
+                },
+                5 => ErrorKind::NoState,
+                code => return Err(DecodeError(format!("Unknown error code {}", code))),
+            },
+            message: r.string()?,
+        },
+        STATE => ServerMessage::State(Snapshot {
+            seat: r.optional(Reader::player)?,
+            state: r.state()?,
+            to_move: r.player()?,
+            scores: [
+                r.signed()? as isize,
+                r.signed()? as isize,
+                r.signed()? as isize,
+            ],
+            bid: r.optional(|r| Ok((r.player()?, r.number()?)))?,
+            trump: r.optional(Reader::suit)?,
+            trick: r.list(|r| Ok((r.player()?, r.card()?)))?,
+            hand: r.list(Reader::card)?,
+            hand_sizes: [r.number()?, r.number()?, r.number()?],
+        }),
+        tag => return Err(DecodeError(format!("Unknown message tag {:#04x}", tag))),
+    };
+    r.end(message)
+}
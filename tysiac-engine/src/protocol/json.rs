@@ -0,0 +1,336 @@
+//! How the fields serde has no shape for of its own are written in JSON,
+//! for the `#[serde(with = …)]` attributes on the messages.
+
+use super::{card, number, player, state, ClientMessage, DecodeError, TableInfo};
+use card_games_lib::{Card, Suit};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tysiac::{Fives, Player, State, StateInput};
+
+/// A value written as a string, such as `"B"` for a seat or `"QH"` for a
+/// card.
+pub(super) trait Named: Sized {
+    fn name(&self) -> String;
+    fn named(text: &str) -> Result<Self, DecodeError>;
+}
+
+impl Named for Player {
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn named(text: &str) -> Result<Player, DecodeError> {
+        player(text)
+    }
+}
+
+impl Named for State {
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn named(text: &str) -> Result<State, DecodeError> {
+        state(text)
+    }
+}
+
+impl Named for Card {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn named(text: &str) -> Result<Card, DecodeError> {
+        card(text)
+    }
+}
+
+impl Named for Suit {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn named(text: &str) -> Result<Suit, DecodeError> {
+        text.parse()
+            .map_err(|_| DecodeError(format!("{:?} is not a suit", text)))
+    }
+}
+
+/// A string, as many JSON readers lose precision past 2^53.
+impl Named for u64 {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn named(text: &str) -> Result<u64, DecodeError> {
+        number(text)
+    }
+}
+
+struct Written<'a, T>(&'a T);
+
+impl<T: Named> Serialize for Written<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.name())
+    }
+}
+
+struct Read<T>(T);
+
+impl<'de, T: Named> Deserialize<'de> for Read<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Read<T>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        T::named(&text)
+            .map(Read)
+            .map_err(|DecodeError(message)| D::Error::custom(message))
+    }
+}
+
+pub(super) mod named {
+    use super::*;
+
+    pub fn serialize<T: Named, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        Written(value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Named, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        Read::deserialize(deserializer).map(|Read(value)| value)
+    }
+}
+
+/// `null` for `None`. Unlike other options the field may not be left out.
+pub(super) mod optional {
+    use super::*;
+
+    pub fn serialize<T: Named, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.as_ref().map(Written).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Named, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        Option::deserialize(deserializer).map(|value| value.map(|Read(value)| value))
+    }
+}
+
+pub(super) mod all {
+    use super::*;
+
+    pub fn serialize<T: Named, S: Serializer>(
+        values: &[T],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(Written))
+    }
+
+    pub fn deserialize<'de, T: Named, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<T>, D::Error> {
+        Vec::deserialize(deserializer).map(|values| values.into_iter().map(|Read(v)| v).collect())
+    }
+}
+
+/// For newtype variants, which serde can't tag: the value goes under a
+/// key of its own beside the tag.
+macro_rules! under {
+    ($module:ident, $key:ident: $type:ty) => {
+        pub(super) mod $module {
+            use super::*;
+
+            #[derive(Serialize)]
+            struct Borrowed<'a> {
+                $key: &'a $type,
+            }
+
+            #[derive(Deserialize)]
+            struct Owned {
+                $key: $type,
+            }
+
+            pub fn serialize<S: Serializer>(
+                $key: &$type,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                Borrowed { $key }.serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<$type, D::Error> {
+                Owned::deserialize(deserializer).map(|owned| owned.$key)
+            }
+        }
+    };
+}
+
+under!(text, text: String);
+under!(table, table: TableInfo);
+under!(tables, tables: Vec<TableInfo>);
+
+#[derive(Serialize, Deserialize)]
+struct Rejected {
+    #[serde(with = "named")]
+    state: State,
+}
+
+/// The state a refused move was for, under `"state"` beside the code.
+pub(super) mod rejected {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(state: &State, serializer: S) -> Result<S::Ok, S::Error> {
+        Rejected { state: *state }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<State, D::Error> {
+        Rejected::deserialize(deserializer).map(|rejected| rejected.state)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Bid {
+    #[serde(with = "named")]
+    player: Player,
+    bid: usize,
+}
+
+/// The highest bid as `{"player":"B","bid":150}`.
+pub(super) mod bid {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bid: &Option<(Player, usize)>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        bid.map(|(player, bid)| Bid { player, bid })
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<(Player, usize)>, D::Error> {
+        Option::<Bid>::deserialize(deserializer).map(|bid| bid.map(|bid| (bid.player, bid.bid)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Play {
+    #[serde(with = "named")]
+    player: Player,
+    #[serde(with = "named")]
+    card: Card,
+}
+
+/// The cards played to the trick as `{"player":"B","card":"QH"}`.
+pub(super) mod trick {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        trick: &[(Player, Card)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(trick.iter().map(|&(player, card)| Play { player, card }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(Player, Card)>, D::Error> {
+        Vec::<Play>::deserialize(deserializer).map(|trick| {
+            trick
+                .into_iter()
+                .map(|play| (play.player, play.card))
+                .collect()
+        })
+    }
+}
+
+/// A move, tagged by what it does rather than the state it is for.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Move {
+    Bid {
+        raise: Option<usize>,
+    },
+    Adjust {
+        raise: usize,
+    },
+    Concede,
+    Pass {
+        #[serde(with = "named")]
+        next: Card,
+        #[serde(with = "named")]
+        prev: Card,
+    },
+    Play {
+        #[serde(with = "named")]
+        card: Card,
+    },
+    Deal {
+        #[serde(with = "named")]
+        seed: u64,
+    },
+    Over,
+}
+
+#[derive(Deserialize)]
+struct Kind {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+const MOVES: [&str; 7] = ["bid", "adjust", "concede", "pass", "play", "deal", "over"];
+
+/// Serde only says an untagged variant didn't match, so a refused move is
+/// read again on its own to say why.
+pub(super) fn client(payload: &str) -> Result<ClientMessage, DecodeError> {
+    serde_json::from_str(payload).map_err(|error| {
+        let why = match serde_json::from_str::<Kind>(payload) {
+            Ok(Kind { kind }) if MOVES.contains(&kind.as_str()) => {
+                input::deserialize(&mut serde_json::Deserializer::from_str(payload)).err()
+            }
+            _ => None,
+        };
+        DecodeError(why.unwrap_or(error).to_string())
+    })
+}
+
+pub(super) mod input {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(input: &StateInput, serializer: S) -> Result<S::Ok, S::Error> {
+        match *input {
+            StateInput::Bidding(raise) => Move::Bid {
+                raise: raise.map(Into::into),
+            },
+            StateInput::AdjustingBid(raise) => Move::Adjust {
+                raise: raise.into(),
+            },
+            StateInput::Concede() => Move::Concede,
+            StateInput::Distrubuting(next, prev) => Move::Pass { next, prev },
+            StateInput::Playing(card) => Move::Play { card },
+            StateInput::Finished(seed) => Move::Deal { seed },
+            StateInput::GameOver() => Move::Over,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StateInput, D::Error> {
+        Ok(match Move::deserialize(deserializer)? {
+            Move::Bid { raise: None } => StateInput::Bidding(None),
+            Move::Bid { raise: Some(raise) } => StateInput::Bidding(Some(fives(raise)?)),
+            Move::Adjust { raise } => StateInput::AdjustingBid(fives(raise)?),
+            Move::Concede => StateInput::Concede(),
+            Move::Pass { next, prev } => StateInput::Distrubuting(next, prev),
+            Move::Play { card } => StateInput::Playing(card),
+            Move::Deal { seed } => StateInput::Finished(seed),
+            Move::Over => StateInput::GameOver(),
+        })
+    }
+
+    fn fives<E: Error>(raise: usize) -> Result<Fives, E> {
+        Fives::new(raise).ok_or_else(|| E::custom(format!("{} is not a multiple of five", raise)))
+    }
+}
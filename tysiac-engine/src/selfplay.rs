@@ -0,0 +1,298 @@
+use crate::agent::{Agent, RandomAgent};
+use crate::heuristic::{Difficulty, HeuristicAgent};
+use crate::monte_carlo::{Budget, Evaluator, MonteCarloAgent};
+use crate::{Error, Tysiac};
+use card_games_lib::{Seat, Seats};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::thread;
+use tysiac::{Player, SomeState, State};
+
+/// An agent to seat in self-play, written `random`, `easy`, `medium`,
+/// `hard`, `mc:<deals>` or `mcdd:<deals>` on the command line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AgentKind {
+    Random,
+    Heuristic(Difficulty),
+    MonteCarlo(usize, Evaluator),
+}
+
+impl AgentKind {
+    pub fn build(self, rng: StdRng) -> Box<dyn Agent> {
+        match self {
+            AgentKind::Random => Box::new(RandomAgent::new(rng)),
+            AgentKind::Heuristic(difficulty) => Box::new(HeuristicAgent::new(rng, difficulty)),
+            AgentKind::MonteCarlo(deals, evaluator) => Box::new(MonteCarloAgent::with_evaluator(
+                rng,
+                Budget::Iterations(deals),
+                evaluator,
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AgentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentKind::Random => write!(f, "random"),
+            AgentKind::Heuristic(Difficulty::Easy) => write!(f, "easy"),
+            AgentKind::Heuristic(Difficulty::Medium) => write!(f, "medium"),
+            AgentKind::Heuristic(Difficulty::Hard) => write!(f, "hard"),
+            AgentKind::MonteCarlo(deals, Evaluator::Rollout) => write!(f, "mc:{}", deals),
+            AgentKind::MonteCarlo(deals, Evaluator::DoubleDummy) => write!(f, "mcdd:{}", deals),
+        }
+    }
+}
+
+impl FromStr for AgentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AgentKind, String> {
+        let deals = |deals: &str| {
+            deals
+                .parse()
+                .map_err(|_| format!("{:?} is not a number of deals", deals))
+        };
+
+        match s.split_once(':') {
+            None => match s {
+                "random" => Ok(AgentKind::Random),
+                "easy" => Ok(AgentKind::Heuristic(Difficulty::Easy)),
+                "medium" => Ok(AgentKind::Heuristic(Difficulty::Medium)),
+                "hard" => Ok(AgentKind::Heuristic(Difficulty::Hard)),
+                _ => Err(format!("Unknown agent {:?}", s)),
+            },
+            Some(("mc", n)) => Ok(AgentKind::MonteCarlo(deals(n)?, Evaluator::Rollout)),
+            Some(("mcdd", n)) => Ok(AgentKind::MonteCarlo(deals(n)?, Evaluator::DoubleDummy)),
+            Some(_) => Err(format!("Unknown agent {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub matches: usize,
+    pub seed: u64,
+    /// The agents of A, B and C in the first match. Each match after that
+    /// moves them round by one seat, so every agent sits in every seat.
+    pub agents: [AgentKind; 3],
+    pub threads: usize,
+    /// Matches still going after this many hands end without a winner.
+    pub max_hands: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            matches: 100,
+            seed: 0,
+            agents: [AgentKind::Heuristic(Difficulty::Hard); 3],
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            max_hands: 200,
+        }
+    }
+}
+
+/// Totals over the matches played.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stats {
+    pub matches: usize,
+    /// Matches that hit `Config::max_hands`.
+    pub unfinished: usize,
+    pub hands: usize,
+    /// The inputs fed, from the first bid to the last card of each hand.
+    pub turns: usize,
+    pub seat_wins: [usize; 3],
+    /// Matches played and won by each agent.
+    pub agent_results: BTreeMap<String, (usize, usize)>,
+    pub bid_total: usize,
+    pub contracts_made: usize,
+    pub marriages: usize,
+}
+
+impl Stats {
+    pub fn merge(&mut self, other: Stats) {
+        self.matches += other.matches;
+        self.unfinished += other.unfinished;
+        self.hands += other.hands;
+        self.turns += other.turns;
+        for (wins, other) in self.seat_wins.iter_mut().zip(other.seat_wins.iter()) {
+            *wins += other;
+        }
+        for (agent, (played, won)) in other.agent_results {
+            let result = self.agent_results.entry(agent).or_default();
+            result.0 += played;
+            result.1 += won;
+        }
+        self.bid_total += other.bid_total;
+        self.contracts_made += other.contracts_made;
+        self.marriages += other.marriages;
+    }
+
+    pub fn seat_win_rate(&self, player: Player) -> f64 {
+        ratio(self.seat_wins[player.index()], self.matches)
+    }
+
+    pub fn agent_win_rate(&self, agent: &str) -> Option<f64> {
+        self.agent_results
+            .get(agent)
+            .map(|(played, won)| ratio(*won, *played))
+    }
+
+    pub fn average_bid(&self) -> f64 {
+        ratio(self.bid_total, self.hands)
+    }
+
+    pub fn contract_success_rate(&self) -> f64 {
+        ratio(self.contracts_made, self.hands)
+    }
+
+    pub fn marriages_per_hand(&self) -> f64 {
+        ratio(self.marriages, self.hands)
+    }
+
+    pub fn turns_per_hand(&self) -> f64 {
+        ratio(self.turns, self.hands)
+    }
+
+    pub fn hands_per_match(&self) -> f64 {
+        ratio(self.hands, self.matches)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "matches: {} ({} unfinished)",
+            self.matches, self.unfinished
+        )?;
+        for player in Player::all() {
+            writeln!(
+                f,
+                "seat {:?} wins: {:.1}%",
+                player,
+                100.0 * self.seat_win_rate(player)
+            )?;
+        }
+        for (agent, (played, won)) in self.agent_results.iter() {
+            writeln!(
+                f,
+                "{} wins: {:.1}% of {}",
+                agent,
+                100.0 * ratio(*won, *played),
+                played
+            )?;
+        }
+        writeln!(f, "hands per match: {:.1}", self.hands_per_match())?;
+        writeln!(f, "turns per hand: {:.1}", self.turns_per_hand())?;
+        writeln!(f, "average bid: {:.1}", self.average_bid())?;
+        writeln!(
+            f,
+            "contracts made: {:.1}%",
+            100.0 * self.contract_success_rate()
+        )?;
+        writeln!(f, "marriages per hand: {:.2}", self.marriages_per_hand())
+    }
+}
+
+/// Plays `config.matches` matches spread over `config.threads` threads.
+/// Match `i` is seeded from `config.seed + i`, so the totals do not depend
+/// on the number of threads.
+pub fn run(config: &Config) -> Result<Stats, Error> {
+    let threads = config.threads.max(1);
+
+    let results: Vec<Result<Stats, Error>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                scope.spawn(move || {
+                    let mut stats = Stats::default();
+                    for index in (thread..config.matches).step_by(threads) {
+                        stats.merge(play_match(config, index)?);
+                    }
+                    Ok(stats)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Self-play threads do not panic"))
+            .collect()
+    });
+
+    let mut stats = Stats::default();
+    for result in results {
+        stats.merge(result?);
+    }
+    Ok(stats)
+}
+
+/// Plays one match to 1000 points, or to `config.max_hands` hands.
+pub fn play_match(config: &Config, index: usize) -> Result<Stats, Error> {
+    let seed = config.seed.wrapping_add(index as u64);
+    let kinds = Seats::new(|player: Player| config.agents[(player.index() + index) % 3]);
+    let mut agents = Seats::new(|player: Player| {
+        kinds[player].build(StdRng::seed_from_u64(seed.wrapping_mul(3) + player as u64))
+    });
+    let mut deals = StdRng::seed_from_u64(seed);
+    let mut game = Tysiac::new(SomeState::random(&mut deals));
+    let mut stats = Stats {
+        matches: 1,
+        ..Stats::default()
+    };
+
+    let winner = loop {
+        let state = game.state().ok_or(Error::NoState)?;
+        match state.state() {
+            State::GameOver => break Some(state.next_player()),
+            _ if stats.hands == config.max_hands => break None,
+            State::Finished => {
+                let (_, bid) = state.bid().expect("A finished hand has a bid");
+                let score = state.bidder_score().expect("A finished hand has a score");
+                stats.hands += 1;
+                stats.bid_total += bid;
+                stats.contracts_made += (score >= bid as isize) as usize;
+            }
+            State::Playing => {
+                let trump = state.trump().cloned();
+                game.turn(&mut agents)?;
+                stats.turns += 1;
+                if let Some(next) = game.state().and_then(SomeState::trump) {
+                    stats.marriages += (Some(next) != trump.as_ref()) as usize;
+                }
+                continue;
+            }
+            _ => {
+                game.turn(&mut agents)?;
+                stats.turns += 1;
+                continue;
+            }
+        }
+        game.deal(deals.gen())?;
+    };
+
+    for player in Player::all() {
+        let won = winner == Some(player);
+        let result = stats
+            .agent_results
+            .entry(kinds[player].to_string())
+            .or_default();
+        result.0 += 1;
+        result.1 += won as usize;
+        stats.seat_wins[player.index()] += won as usize;
+    }
+    stats.unfinished += winner.is_none() as usize;
+
+    Ok(stats)
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
@@ -0,0 +1,585 @@
+use crate::lobby::{Occupant, Table};
+use crate::protocol::{
+    read_frame, write_frame, ClientMessage, Encoding, ErrorKind, Preset, ServerMessage, Snapshot,
+    TableInfo, Wire, MAX_FRAME, PROTOCOL_VERSION,
+};
+use crate::terminal::describe_input;
+use card_games_lib::Seat;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::Message;
+use tysiac::{Player, StateInput};
+
+pub type ConnectionId = usize;
+
+/// How long a WebSocket connection waits for a message before sending what
+/// the hub has queued for it.
+const WEBSOCKET_POLL: Duration = Duration::from_millis(20);
+
+struct Connection {
+    sender: Sender<ServerMessage>,
+    /// The table, and the seat unless only watching.
+    place: Option<(String, Option<Player>)>,
+}
+
+/// The lobby: its tables and the connections at them. Messages for each
+/// connection go to the channel it was connected with, so the hub itself
+/// does no IO.
+pub struct Hub {
+    tables: BTreeMap<String, Table>,
+    connections: HashMap<ConnectionId, Connection>,
+    next_connection: ConnectionId,
+    next_table: usize,
+    rng: StdRng,
+}
+
+impl Hub {
+    pub fn new(seed: u64) -> Hub {
+        Hub {
+            tables: BTreeMap::new(),
+            connections: HashMap::new(),
+            next_connection: 0,
+            next_table: 1,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn connect(&mut self, sender: Sender<ServerMessage>) -> ConnectionId {
+        let id = self.next_connection;
+        self.next_connection += 1;
+        self.connections.insert(
+            id,
+            Connection {
+                sender,
+                place: None,
+            },
+        );
+        id
+    }
+
+    /// Keeps the seat of a connection dropped during a match for a
+    /// `Rejoin`, with a bot playing it until then so the others are not
+    /// stuck. Before the match a dropped connection leaves its seat.
+    pub fn disconnect(&mut self, connection: ConnectionId) {
+        let place = self.connections.remove(&connection).and_then(|c| c.place);
+        match place {
+            Some((table, Some(seat))) if !self.tables[&table].started => {
+                self.leave(connection, table, Some(seat))
+            }
+            Some((table, Some(seat))) => {
+                if let Some(Occupant::Human { connection, .. }) = self.occupant(&table, seat) {
+                    *connection = None;
+                }
+                self.broadcast(&table, ServerMessage::Disconnected { seat });
+
+                let t = self.tables.get_mut(&table).expect("Placed at a table");
+                let mut events = vec![format!("A bot plays for {:?} until they rejoin", seat)];
+                events.extend(t.advance(&mut self.rng));
+                self.after_moves(&table, events);
+            }
+            Some((table, None)) => self.leave(connection, table, None),
+            None => {}
+        }
+    }
+
+    pub fn handle(&mut self, connection: ConnectionId, message: ClientMessage) {
+        let place = match self.connections.get(&connection) {
+            Some(c) => c.place.clone(),
+            None => return,
+        };
+
+        match (message, place) {
+            (ClientMessage::Hello { version, encoding }, _) => {
+                if version == PROTOCOL_VERSION {
+                    self.send(connection, ServerMessage::Welcome { version, encoding })
+                } else {
+                    let message = ServerMessage::Error {
+                        kind: ErrorKind::Version {
+                            supported: PROTOCOL_VERSION,
+                        },
+                        message: format!("This server speaks version {}", PROTOCOL_VERSION),
+                    };
+                    self.send(connection, message)
+                }
+            }
+            (ClientMessage::List, _) => self.send(connection, ServerMessage::Tables(self.tables())),
+            (
+                ClientMessage::Create {
+                    table,
+                    preset,
+                    name,
+                },
+                None,
+            ) => {
+                if self.tables.contains_key(&table) {
+                    let message = format!("There is already a table called {}", table);
+                    return self.send(connection, error(&message));
+                }
+                self.sit(connection, table, preset, name)
+            }
+            (ClientMessage::Join { table, name }, None) => match self.tables.get(&table) {
+                Some(t) => {
+                    let preset = t.preset;
+                    self.sit(connection, table, preset, name)
+                }
+                None => self.send(connection, error("There is no such table")),
+            },
+            (ClientMessage::Find { preset, name }, None) => {
+                let table = self.find(preset);
+                self.sit(connection, table, preset, name)
+            }
+            (ClientMessage::Spectate { table }, None) => self.spectate(connection, table),
+            (ClientMessage::Rejoin { table, token }, None) => self.rejoin(connection, table, token),
+            (ClientMessage::Leave, Some((table, seat))) => self.leave(connection, table, seat),
+            (ClientMessage::Ready, Some((table, Some(seat)))) => {
+                let t = self.tables.get_mut(&table).expect("Seated at a table");
+                if t.started {
+                    return self.send(connection, error("The match has already started"));
+                }
+                if let Some(Occupant::Human { ready, .. }) = &mut t.seats[seat] {
+                    *ready = true;
+                }
+                self.broadcast_info(&table);
+                self.start_if_ready(&table);
+            }
+            (ClientMessage::FillBots, Some((table, Some(_)))) => {
+                let t = self.tables.get_mut(&table).expect("Seated at a table");
+                if t.started {
+                    return self.send(connection, error("The match has already started"));
+                }
+                t.fill_bots(&mut self.rng);
+                self.broadcast_info(&table);
+                self.start_if_ready(&table);
+            }
+            (ClientMessage::Say(text), Some((table, Some(seat)))) => {
+                // Text frames end at a line break
+                let text = text.chars().filter(|c| !c.is_control()).collect();
+                self.broadcast(&table, ServerMessage::Chat { seat, text });
+            }
+            (ClientMessage::Move(input), Some((table, Some(seat)))) => {
+                self.play(connection, table, seat, input)
+            }
+            (ClientMessage::Create { .. }, Some(_))
+            | (ClientMessage::Join { .. }, Some(_))
+            | (ClientMessage::Find { .. }, Some(_))
+            | (ClientMessage::Spectate { .. }, Some(_))
+            | (ClientMessage::Rejoin { .. }, Some(_)) => {
+                self.send(connection, error("Leave your table first"))
+            }
+            (_, Some((_, None))) => self.send(connection, error("Spectators only watch")),
+            (_, None) => self.send(connection, error("Join a table first")),
+        }
+    }
+
+    /// The open table playing `preset` with the most players and a free
+    /// seat, or the name for a new one.
+    fn find(&mut self, preset: Preset) -> String {
+        let open = self
+            .tables
+            .values()
+            .filter(|t| t.preset == preset && !t.started && t.free_seat().is_some())
+            .max_by_key(|t| t.seats.iter().filter(|(_, o)| o.is_some()).count());
+        if let Some(t) = open {
+            return t.name.clone();
+        }
+        loop {
+            let name = format!("{}-{}", preset, self.next_table);
+            self.next_table += 1;
+            if !self.tables.contains_key(&name) {
+                return name;
+            }
+        }
+    }
+
+    /// Seats `name` at `table`, opening it with `preset` if there is none.
+    fn sit(&mut self, connection: ConnectionId, table: String, preset: Preset, name: String) {
+        if let Err(message) =
+            check_name("Table names", &table, true).and_then(|_| check_name("Names", &name, false))
+        {
+            return self.send(connection, error(&message));
+        }
+
+        let token = format!("{:016x}", self.rng.gen::<u64>());
+        if !self.tables.contains_key(&table) {
+            let deal = tysiac::SomeState::random(&mut self.rng);
+            self.tables
+                .insert(table.clone(), Table::new(table.clone(), preset, deal));
+        }
+        let t = self.tables.get_mut(&table).expect("Inserted above");
+        let seat = match t.free_seat() {
+            Some(seat) => seat,
+            None => return self.send(connection, error("The table is full")),
+        };
+        t.seats[seat] = Some(Occupant::Human {
+            name: name.clone(),
+            token: token.clone(),
+            connection: Some(connection),
+            ready: false,
+        });
+
+        self.place(connection, Some((table.clone(), Some(seat))));
+        self.send(
+            connection,
+            ServerMessage::Seated {
+                table: table.clone(),
+                seat,
+                token,
+            },
+        );
+        self.broadcast(&table, ServerMessage::Joined { seat, name });
+        self.broadcast_info(&table);
+    }
+
+    fn spectate(&mut self, connection: ConnectionId, table: String) {
+        let t = match self.tables.get_mut(&table) {
+            Some(t) => t,
+            None => return self.send(connection, error("There is no such table")),
+        };
+        t.spectators.push(connection);
+        let snapshot = match t.started {
+            true => t.game.view(Player::A).as_ref().map(Snapshot::spectator),
+            false => None,
+        };
+
+        self.place(connection, Some((table.clone(), None)));
+        self.broadcast_info(&table);
+        if let Some(snapshot) = snapshot {
+            self.send(connection, ServerMessage::State(snapshot));
+        }
+    }
+
+    fn rejoin(&mut self, connection: ConnectionId, table: String, token: String) {
+        let seat = self.tables.get(&table).and_then(|t| {
+            t.seats
+                .iter()
+                .find(|(_, o)| matches!(o, Some(Occupant::Human { token: t, .. }) if *t == token))
+                .map(|(seat, _)| seat)
+        });
+        let seat = match seat {
+            Some(seat) => seat,
+            None => return self.send(connection, error("No seat has that token")),
+        };
+
+        if let Some(Occupant::Human {
+            connection: current,
+            ..
+        }) = self.occupant(&table, seat)
+        {
+            if let Some(old) = current.replace(connection) {
+                self.place(old, None);
+            }
+        }
+        self.place(connection, Some((table.clone(), Some(seat))));
+        self.send(
+            connection,
+            ServerMessage::Seated {
+                table: table.clone(),
+                seat,
+                token,
+            },
+        );
+        self.broadcast(&table, ServerMessage::Reconnected { seat });
+        if self.tables[&table].started {
+            self.send_snapshots(&table);
+        } else {
+            self.send(connection, ServerMessage::Table(self.tables[&table].info()));
+        }
+    }
+
+    /// A seat left during a match goes to a bot, so the others can finish.
+    fn leave(&mut self, connection: ConnectionId, table: String, seat: Option<Player>) {
+        if let Some(seat) = seat {
+            self.broadcast(&table, ServerMessage::Left { seat });
+        }
+        self.place(connection, None);
+
+        let t = self.tables.get_mut(&table).expect("Placed at a table");
+        let mut events = vec![];
+        match seat {
+            Some(seat) if t.started => {
+                t.seats[seat] = Some(t.bot(&mut self.rng));
+                events.push(format!("A bot takes {:?}'s seat", seat));
+                events.extend(t.advance(&mut self.rng));
+            }
+            Some(seat) => t.seats[seat] = None,
+            None => t.spectators.retain(|c| *c != connection),
+        }
+
+        if t.is_abandoned() {
+            self.tables.remove(&table);
+            return;
+        }
+        let started = t.started;
+        for event in events {
+            self.broadcast(&table, ServerMessage::Event(event));
+        }
+        if started {
+            self.send_snapshots(&table);
+        } else {
+            self.broadcast_info(&table);
+        }
+    }
+
+    fn start_if_ready(&mut self, table: &str) {
+        let t = self.tables.get_mut(table).expect("Checked by the caller");
+        if t.started || !t.is_ready() {
+            return;
+        }
+        t.start(&mut self.rng);
+        let mut events = vec!["The match starts".to_owned()];
+        events.extend(t.advance(&mut self.rng));
+        self.after_moves(table, events);
+    }
+
+    fn play(&mut self, connection: ConnectionId, table: String, seat: Player, input: StateInput) {
+        let t = self.tables.get_mut(&table).expect("Seated at a table");
+        let result = if t.started {
+            t.game
+                .feed(seat.index(), input.clone())
+                .map_err(|e| ServerMessage::error(&e))
+        } else {
+            Err(error("The match has not started"))
+        };
+        if let Err(message) = result {
+            return self.send(connection, message);
+        }
+
+        let mut events = vec![describe_input(seat, &input)];
+        events.extend(t.advance(&mut self.rng));
+        self.after_moves(&table, events);
+    }
+
+    fn after_moves(&self, table: &str, events: Vec<String>) {
+        for event in events {
+            self.broadcast(table, ServerMessage::Event(event));
+        }
+        self.send_snapshots(table);
+        if !self.tables[table].started {
+            self.broadcast_info(table);
+        }
+    }
+
+    fn send_snapshots(&self, table: &str) {
+        let t = &self.tables[table];
+        for (seat, connection) in t.connections() {
+            let snapshot = match seat {
+                Some(seat) => {
+                    Snapshot::new(&t.game.view(seat).expect("Tables always have a state"))
+                }
+                None => Snapshot::spectator(
+                    &t.game.view(Player::A).expect("Tables always have a state"),
+                ),
+            };
+            self.send(connection, ServerMessage::State(snapshot));
+        }
+    }
+
+    fn broadcast_info(&self, table: &str) {
+        if let Some(t) = self.tables.get(table) {
+            self.broadcast(table, ServerMessage::Table(t.info()));
+        }
+    }
+
+    fn broadcast(&self, table: &str, message: ServerMessage) {
+        if let Some(t) = self.tables.get(table) {
+            for (_, connection) in t.connections() {
+                self.send(connection, message.clone());
+            }
+        }
+    }
+
+    fn send(&self, connection: ConnectionId, message: ServerMessage) {
+        if let Some(c) = self.connections.get(&connection) {
+            // A closed channel means the connection is going away
+            let _ = c.sender.send(message);
+        }
+    }
+
+    fn place(&mut self, connection: ConnectionId, place: Option<(String, Option<Player>)>) {
+        if let Some(c) = self.connections.get_mut(&connection) {
+            c.place = place;
+        }
+    }
+
+    fn occupant(&mut self, table: &str, seat: Player) -> Option<&mut Occupant> {
+        self.tables.get_mut(table)?.seats[seat].as_mut()
+    }
+
+    /// Every table, sorted by name.
+    pub fn tables(&self) -> Vec<TableInfo> {
+        self.tables.values().map(Table::info).collect()
+    }
+}
+
+/// Serves a `Hub` over TCP, with the framing of `read_frame` and
+/// `write_frame`, or over WebSocket, one message per WebSocket message.
+#[derive(Clone)]
+pub struct Server {
+    hub: Arc<Mutex<Hub>>,
+}
+
+impl Server {
+    pub fn new(hub: Hub) -> Server {
+        Server {
+            hub: Arc::new(Mutex::new(hub)),
+        }
+    }
+
+    pub fn hub(&self) -> &Arc<Mutex<Hub>> {
+        &self.hub
+    }
+
+    /// Accepts connections until the listener fails, each on its own
+    /// threads. Connections start in `Encoding::Text`.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let server = self.clone();
+            let stream = stream?;
+            thread::spawn(move || server.handle_connection(stream));
+        }
+        Ok(())
+    }
+
+    pub fn handle_connection(&self, stream: TcpStream) -> io::Result<()> {
+        let (sender, receiver) = mpsc::channel::<ServerMessage>();
+        let connection = self.lock().connect(sender);
+
+        let mut writer = stream.try_clone()?;
+        thread::spawn(move || {
+            let mut encoding = Encoding::Text;
+            for message in receiver {
+                let payload = message.encode(encoding);
+                if write_frame(&mut writer, encoding, &payload).is_err() {
+                    break;
+                }
+                // The welcome itself goes out in the old encoding
+                if let ServerMessage::Welcome { encoding: next, .. } = message {
+                    encoding = next;
+                }
+            }
+        });
+
+        let mut reader = BufReader::new(stream);
+        let mut encoding = Encoding::Text;
+        while let Ok(Some(payload)) = read_frame(&mut reader, encoding) {
+            self.receive(connection, &mut encoding, &payload);
+        }
+
+        self.lock().disconnect(connection);
+        Ok(())
+    }
+
+    /// Like `serve`, for WebSocket connections.
+    pub fn serve_websocket(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let server = self.clone();
+            let stream = stream?;
+            thread::spawn(move || server.handle_websocket(stream));
+        }
+        Ok(())
+    }
+
+    /// Text and JSON payloads go in text messages, binary ones in binary
+    /// messages.
+    pub fn handle_websocket(&self, stream: TcpStream) -> io::Result<()> {
+        let config = WebSocketConfig::default().max_message_size(Some(MAX_FRAME));
+        let mut socket = tungstenite::accept_with_config(stream, Some(config))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        // One thread reads and writes, so reads give way to queued messages
+        socket.get_ref().set_read_timeout(Some(WEBSOCKET_POLL))?;
+
+        let (sender, receiver) = mpsc::channel::<ServerMessage>();
+        let connection = self.lock().connect(sender);
+        let mut encoding = Encoding::Text;
+        let mut sending = Encoding::Text;
+
+        'connection: loop {
+            for message in receiver.try_iter() {
+                let payload = message.encode(sending);
+                let frame = match sending {
+                    Encoding::Binary => Message::binary(payload),
+                    _ => Message::text(String::from_utf8_lossy(&payload).into_owned()),
+                };
+                if socket.send(frame).is_err() {
+                    break 'connection;
+                }
+                if let ServerMessage::Welcome { encoding: next, .. } = message {
+                    sending = next;
+                }
+            }
+
+            let payload = match socket.read() {
+                Ok(message @ Message::Text(_)) | Ok(message @ Message::Binary(_)) => {
+                    message.into_data()
+                }
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    continue
+                }
+                Err(_) => break,
+            };
+            self.receive(connection, &mut encoding, &payload);
+        }
+
+        self.lock().disconnect(connection);
+        Ok(())
+    }
+
+    /// Hands one payload from `connection` to the hub. A `Hello` the hub
+    /// accepts switches `encoding` for the payloads after it.
+    fn receive(&self, connection: ConnectionId, encoding: &mut Encoding, payload: &[u8]) {
+        if *encoding == Encoding::Text && payload.iter().all(u8::is_ascii_whitespace) {
+            return;
+        }
+        let message = ClientMessage::decode(*encoding, payload);
+        if let Ok(ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            encoding: next,
+        }) = message
+        {
+            *encoding = next;
+        }
+        let mut hub = self.lock();
+        match message {
+            Ok(message) => hub.handle(connection, message),
+            Err(e) => hub.send(connection, error(&e.0)),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Hub> {
+        self.hub
+            .lock()
+            .expect("The hub does not panic while locked")
+    }
+}
+
+fn error(message: &str) -> ServerMessage {
+    ServerMessage::request_error(message)
+}
+
+/// Names may not be empty or hold control characters, and table names are
+/// one word.
+fn check_name(kind: &str, name: &str, one_word: bool) -> Result<(), String> {
+    if name.is_empty() || name.chars().any(char::is_control) {
+        return Err(format!(
+            "{} cannot be empty or hold control characters",
+            kind
+        ));
+    }
+    if one_word && name.chars().any(char::is_whitespace) {
+        return Err(format!("{} cannot hold spaces", kind));
+    }
+    Ok(())
+}
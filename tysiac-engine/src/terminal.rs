@@ -0,0 +1,164 @@
+use crate::agent::View;
+use crate::Error;
+use card_games_lib::{Notation, Seat, TysiacOrder};
+use std::fmt::Write;
+use tysiac::{Fives, Player, State, StateError, StateInput};
+
+/// The table as `view`'s seat sees it: the scores, the bid, trump, the
+/// trick on the table and the seat's hand sorted by suit.
+pub fn render_view(view: &View<'_>) -> String {
+    let mut out = String::new();
+    let scores: Vec<String> = Player::all()
+        .map(|player| format!("{:?} {}", player, view.points(player)))
+        .collect();
+    writeln!(out, "Scores: {}", scores.join("  ")).expect("Writing to a String");
+
+    if let Some((player, bid)) = view.bid() {
+        writeln!(out, "Bid: {} by {:?}", bid, player).expect("Writing to a String");
+    }
+    if let Some(trump) = view.trump() {
+        writeln!(out, "Trump: {}", trump.description().name()).expect("Writing to a String");
+    }
+    if let Some(trick) = view.trick().filter(|trick| !trick.is_empty()) {
+        let plays: Vec<String> = trick
+            .plays()
+            .map(|(player, card)| format!("{:?} {}", player, show(card.description())))
+            .collect();
+        writeln!(out, "Trick: {}", plays.join(", ")).expect("Writing to a String");
+    }
+    if let Some(hand) = view.hand() {
+        let mut hand = hand.clone();
+        hand.sort(TysiacOrder);
+        let suits: Vec<String> = hand
+            .group_by_suit()
+            .values()
+            .map(|cards| {
+                let cards: Vec<String> =
+                    cards.iter().map(|card| show(card.description())).collect();
+                cards.join(" ")
+            })
+            .collect();
+        writeln!(out, "Your hand ({:?}): {}", view.seat(), suits.join(" | "))
+            .expect("Writing to a String");
+    }
+
+    out
+}
+
+/// What to type in `state`.
+pub fn help(state: State) -> &'static str {
+    match state {
+        State::Bidding => "Type your bid, e.g. 120, or pass",
+        State::AdjustingBid => {
+            "Type a new bid, keep to stay at the current bid, or concede to give up the hand"
+        }
+        State::Distrubuting => "Type the card for the next player, then the previous, e.g. 9S JD",
+        State::Playing => "Type a card to play, e.g. QH",
+        State::Finished => "Press enter to deal the next hand",
+        State::GameOver => "The game is over",
+    }
+}
+
+/// Reads a line typed by the seat to move, checking what can be checked
+/// before the game sees it. Bids are typed as the total, not the increase.
+pub fn parse_input(view: &View<'_>, line: &str) -> Result<StateInput, String> {
+    let line = line.trim();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let current = view.bid().map_or(0, |(_, bid)| bid);
+    let card = |word: &str| {
+        word.parse::<card_games_lib::Card>()
+            .map_err(|_| format!("{:?} is not a card", word))
+    };
+    let raise = |word: &str, at_least: usize| {
+        let bid: usize = word
+            .parse()
+            .map_err(|_| format!("{:?} is not a bid", word))?;
+        if bid < at_least {
+            return Err(format!("Bid at least {}", at_least));
+        }
+        Fives::new(bid - current).ok_or_else(|| format!("{} is not a multiple of five", bid))
+    };
+
+    match view.state() {
+        State::Bidding => match words.as_slice() {
+            ["pass"] => Ok(StateInput::Bidding(None)),
+            [bid] => Ok(StateInput::Bidding(Some(raise(bid, current + 5)?))),
+            _ => Err(help(State::Bidding).to_owned()),
+        },
+        State::AdjustingBid => match words.as_slice() {
+            ["keep"] | [] => Ok(StateInput::AdjustingBid(Fives::zero())),
+            ["concede"] => Ok(StateInput::Concede()),
+            [bid] => Ok(StateInput::AdjustingBid(raise(bid, current)?)),
+            _ => Err(help(State::AdjustingBid).to_owned()),
+        },
+        State::Distrubuting => match words.as_slice() {
+            [next, prev] => Ok(StateInput::Distrubuting(card(next)?, card(prev)?)),
+            _ => Err(help(State::Distrubuting).to_owned()),
+        },
+        State::Playing => match words.as_slice() {
+            [played] => Ok(StateInput::Playing(card(played)?)),
+            _ => Err(help(State::Playing).to_owned()),
+        },
+        State::Finished => Err("The host deals the next hand".to_owned()),
+        State::GameOver => Ok(StateInput::GameOver()),
+    }
+}
+
+/// What `player` did, for the other seats to read.
+pub fn describe_input(player: Player, input: &StateInput) -> String {
+    match input {
+        StateInput::Bidding(None) => format!("{:?} passes", player),
+        StateInput::Bidding(Some(raise)) => {
+            format!("{:?} raises the bid by {}", player, usize::from(*raise))
+        }
+        StateInput::AdjustingBid(raise) if usize::from(*raise) == 0 => {
+            format!("{:?} keeps the bid", player)
+        }
+        StateInput::AdjustingBid(raise) => {
+            format!("{:?} raises the bid by {}", player, usize::from(*raise))
+        }
+        StateInput::Concede() => format!("{:?} concedes the hand", player),
+        StateInput::Distrubuting(..) => format!("{:?} passes a card to each player", player),
+        StateInput::Playing(card) => format!("{:?} plays {}", player, show(*card)),
+        StateInput::Finished(_) => "The next hand is dealt".to_owned(),
+        StateInput::GameOver() => "The game is over".to_owned(),
+    }
+}
+
+/// `error` as a sentence for the player who caused it.
+pub fn describe_error(error: &Error) -> String {
+    match error {
+        Error::Game(card_games_lib::Error::StepError(error)) => message(error).to_owned(),
+        Error::Game(card_games_lib::Error::NotInCorrectStateError { held, given }) => {
+            format!(
+                "That move is for {:?}, but the game is in {:?}",
+                given, held
+            )
+        }
+        Error::IncorrectPlayer {
+            current,
+            attempted: Some(attempted),
+        } => format!("It is {:?}'s turn, not {:?}'s", current, attempted),
+        Error::IncorrectPlayer {
+            current,
+            attempted: None,
+        } => format!("It is {:?}'s turn", current),
+        Error::NoState => "The game has ended".to_owned(),
+    }
+}
+
+fn message(error: &StateError) -> &str {
+    match error {
+        StateError::Bidding(message)
+        | StateError::AdjustingBid(message)
+        | StateError::Concede(message)
+        | StateError::Distrubuting(message)
+        | StateError::Playing(message)
+        | StateError::Finished(message)
+        | StateError::GameOver(message) => message,
+    }
+}
+
+fn show(card: card_games_lib::Card) -> String {
+    Notation::Unicode.show(card).to_string()
+}
@@ -1,4 +1,5 @@
 use super::*;
+use card_games_lib::Seat;
 use rand::thread_rng;
 use tysiac::{Bidding, Fives, Player};
 
@@ -33,3 +34,1082 @@ fn it_works() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn random_agents_play_a_hand() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = Tysiac::new(SomeState::random(&mut rng));
+        let mut agents = Seats::new(|player: Player| {
+            RandomAgent::new(StdRng::seed_from_u64(seed * 3 + player as u64))
+        });
+
+        game.play_hand(&mut agents)?;
+
+        let state = game.state().expect("The hand ends in a state");
+        assert_eq!(state.state(), State::Finished);
+        let (bid_winner, bid) = state.bid().expect("A finished hand has a bid");
+        assert!(bid >= 100);
+        assert_eq!(game.game().points(bid_winner).abs(), bid as isize);
+
+        let dealer = state.next_player();
+        assert_eq!(dealer, Player::A);
+        assert!(matches!(
+            game.feed(dealer.index(), StateInput::Finished(seed)),
+            Err(Error::Game(card_games_lib::Error::StepError(
+                StateError::Finished(_)
+            )))
+        ));
+
+        game.deal(seed)?;
+        assert_eq!(game.game().dealer(), Player::A);
+        assert_eq!(
+            game.state().and_then(SomeState::bid),
+            Some((Player::B, 100))
+        );
+    }
+
+    Ok(())
+}
+
+/// The hands of A, B and C in turn, then the prikup.
+fn stacked_deck() -> Vec<tysiac::Card> {
+    "KH QH AH TH KD QD AS \
+     TS KS 9S JS JD 9D TD \
+     AC TC KC QC JC 9C AD \
+     9H JH QS"
+        .split_whitespace()
+        .map(|card| card.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn heuristic_hand_evaluation() {
+    use card_games_lib::Pile;
+
+    let heuristics = Heuristics::new(Difficulty::Medium);
+    let hand: Pile<tysiac::Card> = "KH QH AH TH 9S JS 9D".parse().unwrap();
+    // 4 + 3 + 11 + 10 + 2 card points, a marriage in hearts, an ace and a ten
+    assert_eq!(heuristics.hand_value(&hand), 30 + 100 + 10);
+    assert_eq!(heuristics.max_bid(&hand, 10), 150);
+    assert_eq!(Heuristics::new(Difficulty::Easy).max_bid(&hand, 0), 120);
+
+    let (first, second) = heuristics.cards_to_pass(&hand).unwrap();
+    assert_eq!(first.to_string(), "9S");
+    assert_eq!(second.to_string(), "9D");
+}
+
+#[test]
+fn heuristic_agent_bids_and_leads_marriage() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut game = Tysiac::new(SomeState::deal(&mut stacked_deck().into_iter()));
+    let mut agents =
+        Seats::new(|_| HeuristicAgent::new(StdRng::seed_from_u64(1), Difficulty::Hard));
+
+    while game.state().map(SomeState::state) != Some(State::Playing) {
+        game.turn(&mut agents)?;
+    }
+
+    // C bids up to 140, A wins at 140 and raises to 250 with the prikup
+    let state = game.state().unwrap();
+    assert_eq!(state.bid(), Some((Player::A, 250)));
+
+    let hand = state.hand(&Player::A).unwrap();
+    assert!(hand.contains(&"QS".parse::<card_games_lib::Card>().unwrap()));
+    assert!(!hand.contains(&"9H".parse::<card_games_lib::Card>().unwrap()));
+    assert!(!hand.contains(&"JH".parse::<card_games_lib::Card>().unwrap()));
+
+    game.turn(&mut agents)?;
+    let state = game.state().unwrap();
+    assert_eq!(state.trump(), Some(&tysiac::Suit::Hearts));
+    assert_eq!(
+        state.trick().unwrap().lead().map(ToString::to_string),
+        Some("QH".to_owned())
+    );
+
+    game.play_hand(&mut agents)?;
+    assert_eq!(game.state().map(SomeState::state), Some(State::Finished));
+
+    Ok(())
+}
+
+#[test]
+fn heuristic_agents_play_hands() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    for seed in 0..20 {
+        let mut game = Tysiac::new(SomeState::random(&mut StdRng::seed_from_u64(seed)));
+        let mut agents = Seats::new(|player: Player| {
+            let difficulty =
+                [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard][player.index()];
+            HeuristicAgent::new(StdRng::seed_from_u64(seed), difficulty)
+        });
+
+        game.play_hand(&mut agents)?;
+        assert_eq!(game.state().map(SomeState::state), Some(State::Finished));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_samples_agree_with_the_view() -> Result<(), Error> {
+    use card_games_lib::{CardSet, Seat, Seats};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut game = Tysiac::new(SomeState::deal(&mut stacked_deck().into_iter()));
+    let mut agents =
+        Seats::new(|_| HeuristicAgent::new(StdRng::seed_from_u64(1), Difficulty::Hard));
+    while game.state().and_then(SomeState::taken).map(|t| t.len()) != Some(3) {
+        game.turn(&mut agents)?;
+    }
+
+    let view = game.view(Player::B).unwrap();
+    let knowledge = Knowledge::new(&view);
+    let hand = CardSet::from(view.hand().unwrap());
+    assert_eq!(knowledge.unseen().len(), 24 - hand.len() - 9);
+
+    let mut rng = StdRng::seed_from_u64(2);
+    for _ in 0..50 {
+        let hands = knowledge.sample(&mut rng);
+        assert_eq!(hands[Player::B], hand);
+        for player in Player::all() {
+            assert_eq!(hands[player].len(), view.hand_len(player));
+            assert!((hands[player] & knowledge.voids(player)).is_empty());
+        }
+        assert_eq!(hands[Player::A] | hands[Player::C], knowledge.unseen());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_samples_place_the_prikup_and_passed_cards() -> Result<(), Error> {
+    use card_games_lib::{CardSet, Pile, Seats};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let set = |cards: &str| CardSet::from(&cards.parse::<Pile<card_games_lib::Card>>().unwrap());
+    let mut game = Tysiac::new(SomeState::deal(&mut stacked_deck().into_iter()));
+    let mut agents =
+        Seats::new(|_| HeuristicAgent::new(StdRng::seed_from_u64(1), Difficulty::Hard));
+    while game.state().map(SomeState::state) != Some(State::Playing) {
+        game.turn(&mut agents)?;
+    }
+    let mut rng = StdRng::seed_from_u64(2);
+
+    // A won the bid, took 9H JH QS and passed 9H to B and JH to C
+    let view = game.view(Player::A).unwrap();
+    assert_eq!(view.prikup(), Some(set("9H JH QS")));
+    let knowledge = Knowledge::new(&view);
+    assert_eq!(knowledge.known(Player::B), set("9H"));
+    assert_eq!(knowledge.known(Player::C), set("JH"));
+    for _ in 0..20 {
+        let hands = knowledge.sample(&mut rng);
+        assert!(hands[Player::B].contains("9H".parse().unwrap()));
+        assert!(hands[Player::C].contains("JH".parse().unwrap()));
+    }
+
+    // B holds 9H and knows A kept the rest of the prikup, but for the one
+    // card A may have passed to C
+    let view = game.view(Player::B).unwrap();
+    assert!(view.passed().is_none());
+    let knowledge = Knowledge::new(&view);
+    assert_eq!(knowledge.known(Player::A), set("JH QS"));
+    let mut passed_to_c = CardSet::EMPTY;
+    let mut kept = 0;
+    for _ in 0..200 {
+        let hands = knowledge.sample(&mut rng);
+        let elsewhere = knowledge.known(Player::A) - hands[Player::A];
+        assert!(elsewhere.len() <= 1);
+        passed_to_c |= elsewhere;
+        kept += elsewhere.is_empty() as usize;
+    }
+    assert_eq!(passed_to_c, set("JH QS"));
+    // A more likely passed C a card of their own
+    assert!(kept > 100);
+
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_agent_plays_a_hand() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::time::Duration;
+
+    let mut game = Tysiac::new(SomeState::random(&mut StdRng::seed_from_u64(3)));
+    let mut agents = Seats::new(|player: Player| -> Box<dyn Agent> {
+        let rng = StdRng::seed_from_u64(player as u64);
+        match player {
+            Player::A => Box::new(MonteCarloAgent::new(rng, Budget::Iterations(4))),
+            _ => Box::new(HeuristicAgent::new(rng, Difficulty::Medium)),
+        }
+    });
+
+    while game.state().map(SomeState::state) != Some(State::Playing) {
+        game.turn(&mut agents)?;
+    }
+    while game.state().unwrap().next_player() != Player::A {
+        game.turn(&mut agents)?;
+    }
+
+    let view = game.view(Player::A).unwrap();
+    let mut search = MonteCarloAgent::new(
+        StdRng::seed_from_u64(4),
+        Budget::Time(Duration::from_millis(5)),
+    );
+    let scores = search.evaluate(&view);
+    assert_eq!(scores.len(), view.legal_cards().len());
+    assert!(scores
+        .iter()
+        .all(|(card, _)| view.legal_cards().contains(*card)));
+
+    game.play_hand(&mut agents)?;
+    assert_eq!(game.state().map(SomeState::state), Some(State::Finished));
+
+    Ok(())
+}
+
+/// The most points `side` can take, by trying every card with `Step`.
+fn exhaustive_points(state: &SomeState, game: &Game, side: Side) -> isize {
+    let playing = match state {
+        SomeState::Playing(playing) => playing,
+        _ => return 0,
+    };
+    let (bid_winner, _) = state.bid().unwrap();
+    let on_side = |player: Player| Side::of(player, bid_winner) == side;
+    let tricks = state.taken().unwrap().len();
+
+    let values = playing.legal_cards().iter().map(|card| {
+        let mut game = game.clone();
+        let (next, result) = state.clone().step(&mut game, StateInput::Playing(card));
+        assert_eq!(result, Ok(()));
+
+        let mut gain = 0;
+        match next.trump() {
+            Some(trump) if state.trump() != Some(trump) && on_side(state.next_player()) => {
+                gain += isize::from(trump.marriage_value());
+            }
+            _ => {}
+        }
+        match next.taken().unwrap().iter().nth(tricks) {
+            Some((winner, trick)) if on_side(*winner) => {
+                gain += trick
+                    .cards()
+                    .map(|c| isize::from(c.rank().point_value()))
+                    .sum::<isize>();
+            }
+            _ => {}
+        }
+        gain + exhaustive_points(&next, &game, side)
+    });
+
+    if on_side(state.next_player()) {
+        values.max().unwrap()
+    } else {
+        values.min().unwrap()
+    }
+}
+
+#[test]
+fn double_dummy_matches_exhaustive_search() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    for seed in 0..6 {
+        let mut game = Tysiac::new(SomeState::random(&mut StdRng::seed_from_u64(seed)));
+        let mut agents =
+            Seats::new(|_| HeuristicAgent::new(StdRng::seed_from_u64(seed), Difficulty::Medium));
+        // Four tricks left, and part of the fifth played for odd seeds
+        let played = 4 * 3 + (seed as usize % 2) * 2;
+        while game
+            .state()
+            .and_then(SomeState::taken)
+            .map_or(0, |t| t.len() * 3)
+            + game
+                .state()
+                .and_then(SomeState::trick)
+                .map_or(0, |t| t.len())
+            != played
+        {
+            game.turn(&mut agents)?;
+        }
+
+        let state = game.state().unwrap();
+        let mut solver = DoubleDummy::new(state).unwrap();
+        let solution = solver.solve();
+        assert_eq!(
+            solution.bidder,
+            exhaustive_points(state, game.game(), Side::Bidder)
+        );
+        assert_eq!(
+            solution.defenders,
+            exhaustive_points(state, game.game(), Side::Defenders)
+        );
+
+        let best = solver.card_values(Side::Bidder).into_iter().map(|(_, v)| v);
+        let bidder_to_move = Side::of(state.next_player(), state.bid().unwrap().0) == Side::Bidder;
+        if bidder_to_move {
+            assert_eq!(best.max(), Some(solution.bidder));
+        } else {
+            assert_eq!(best.min(), Some(solution.bidder));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn double_dummy_solves_a_whole_hand() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut game = Tysiac::new(SomeState::deal(&mut stacked_deck().into_iter()));
+    let mut agents =
+        Seats::new(|_| HeuristicAgent::new(StdRng::seed_from_u64(1), Difficulty::Hard));
+    while game.state().map(SomeState::state) != Some(State::Playing) {
+        game.turn(&mut agents)?;
+    }
+
+    // A holds the marriages in hearts and diamonds and three aces
+    let solution = DoubleDummy::new(game.state().unwrap()).unwrap().solve();
+    assert!(solution.bidder >= 180);
+    assert!(solution.defenders <= 120);
+
+    Ok(())
+}
+
+#[test]
+fn double_dummy_legal_cards_match_the_game() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = Tysiac::new(SomeState::random(&mut rng));
+        let mut agents = Seats::new(|player: Player| {
+            RandomAgent::new(StdRng::seed_from_u64(seed * 3 + player as u64))
+        });
+        while game.state().map(SomeState::state) != Some(State::Playing) {
+            game.turn(&mut agents)?;
+        }
+
+        // One solver follows the whole hand, and a fresh one is built for
+        // every position
+        let mut solver = DoubleDummy::new(game.state().unwrap()).unwrap();
+        while let Some(state @ SomeState::Playing(playing)) = game.state() {
+            let legal = playing.legal_cards();
+            assert_eq!(solver.legal_cards(), legal);
+            assert_eq!(DoubleDummy::new(state).unwrap().legal_cards(), legal);
+
+            let cards: Vec<_> = legal.iter().collect();
+            let card = cards[rng.gen_range(0, cards.len())];
+            game.feed(state.next_player().index(), StateInput::Playing(card))?;
+            solver.play(card);
+        }
+        assert_eq!(game.state().map(SomeState::state), Some(State::Finished));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_agent_evaluates_with_double_dummy() -> Result<(), Error> {
+    use card_games_lib::Seats;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut game = Tysiac::new(SomeState::random(&mut StdRng::seed_from_u64(5)));
+    let mut agents =
+        Seats::new(|_| HeuristicAgent::new(StdRng::seed_from_u64(5), Difficulty::Medium));
+    while game.state().and_then(SomeState::taken).map(|t| t.len()) != Some(5) {
+        game.turn(&mut agents)?;
+    }
+
+    let state = game.state().unwrap();
+    let view = game.view(state.next_player()).unwrap();
+    let mut search = MonteCarloAgent::with_evaluator(
+        StdRng::seed_from_u64(6),
+        Budget::Iterations(3),
+        Evaluator::DoubleDummy,
+    );
+    let scores = search.evaluate(&view);
+    assert_eq!(scores.len(), view.legal_cards().len());
+    assert!(scores.iter().all(|(_, score)| *score >= 0.0));
+
+    match search.act(&view) {
+        StateInput::Playing(card) => assert!(view.legal_cards().contains(card)),
+        input => panic!("Expected a card, got {:?}", input),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bid_strength_of_strong_and_weak_hands() -> Result<(), Error> {
+    use card_games_lib::Pile;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let strong: Pile<tysiac::Card> = "KH QH AH TH KD QD AD TD AS AC".parse().unwrap();
+    let estimate = estimate_bid(&strong, 40, &mut rng)?.unwrap();
+    assert_eq!(estimate.samples, 40);
+    assert_eq!(estimate.marriage_chance, 1.0);
+    assert!(estimate.expected_marriage_points >= 100.0);
+    assert_eq!(estimate.success(100), 1.0);
+    assert!(estimate.recommended_max_bid().unwrap() >= 200);
+
+    let weak: Pile<tysiac::Card> = "9S JS 9C JC 9D JD QS".parse().unwrap();
+    let estimate = estimate_bid(&weak, 40, &mut rng)?.unwrap();
+    assert_eq!(estimate.recommended_max_bid(), None);
+    assert!(estimate.expected_card_points < 60.0);
+    assert!(estimate
+        .success
+        .windows(2)
+        .all(|pair| pair[0].1 >= pair[1].1));
+
+    let eight: Pile<tysiac::Card> = "9S JS 9C JC 9D JD QS KS".parse().unwrap();
+    assert_eq!(estimate_bid(&eight, 1, &mut rng), Ok(None));
+
+    Ok(())
+}
+
+#[test]
+fn selfplay_totals_do_not_depend_on_threads() -> Result<(), Error> {
+    use selfplay::{AgentKind, Config};
+
+    let agents: Vec<AgentKind> = "random,medium,hard"
+        .split(',')
+        .map(|agent| agent.parse().unwrap())
+        .collect();
+    let config = Config {
+        matches: 4,
+        seed: 9,
+        agents: [agents[0], agents[1], agents[2]],
+        threads: 1,
+        max_hands: 5,
+    };
+
+    let stats = selfplay::run(&config)?;
+    assert_eq!(stats.matches, 4);
+    assert!(stats.hands > 0 && stats.hands <= 20);
+    assert_eq!(stats.agent_results["random"].0, 4);
+    assert_eq!(
+        stats.seat_wins.iter().sum::<usize>() + stats.unfinished,
+        stats.matches
+    );
+    assert!(stats.average_bid() >= 100.0);
+    assert!(stats.contract_success_rate() <= 1.0);
+
+    let threaded = selfplay::run(&Config {
+        threads: 3,
+        ..config
+    })?;
+    assert_eq!(threaded, stats);
+
+    Ok(())
+}
+
+#[test]
+fn selfplay_agent_names() {
+    use selfplay::AgentKind;
+
+    for name in ["random", "easy", "medium", "hard", "mc:20", "mcdd:4"].iter() {
+        let agent: AgentKind = name.parse().unwrap();
+        assert_eq!(agent.to_string(), *name);
+    }
+    assert!("mc:lots".parse::<AgentKind>().is_err());
+    assert!("expert".parse::<AgentKind>().is_err());
+}
+
+#[test]
+fn terminal_parses_and_describes_moves() -> Result<(), Error> {
+    use terminal::{describe_error, describe_input, parse_input, render_view};
+
+    let mut game = Tysiac::new(SomeState::deal(&mut stacked_deck().into_iter()));
+    let view = game.view(Player::B).unwrap();
+    assert_eq!(
+        render_view(&view),
+        "Scores: A 0  B 0  C 0\n\
+         Bid: 100 by A\n\
+         Your hand (B): 10♦ J♦ 9♦ | 10♠ K♠ J♠ 9♠\n"
+    );
+
+    assert_eq!(parse_input(&view, " pass "), Ok(StateInput::Bidding(None)));
+    assert_eq!(
+        parse_input(&view, "120"),
+        Ok(StateInput::Bidding(Fives::new(20)))
+    );
+    assert_eq!(
+        parse_input(&view, "100"),
+        Err("Bid at least 105".to_owned())
+    );
+    assert_eq!(
+        parse_input(&view, "12O"),
+        Err("\"12O\" is not a bid".to_owned())
+    );
+    assert_eq!(
+        describe_input(Player::B, &StateInput::Bidding(Fives::new(20))),
+        "B raises the bid by 20"
+    );
+
+    let error = game.feed(0, StateInput::Bidding(None)).unwrap_err();
+    assert_eq!(describe_error(&error), "It is B's turn, not A's");
+
+    game.feed(1, StateInput::Bidding(None))?;
+    game.feed(2, StateInput::Bidding(None))?;
+    game.feed(0, StateInput::AdjustingBid(Fives::zero()))?;
+    let view = game.view(Player::A).unwrap();
+    let input = parse_input(&view, "9S 9S").unwrap();
+    let error = game.feed(0, input).unwrap_err();
+    assert_eq!(
+        describe_error(&error),
+        "Trying to pass Card(Nine, Spades), which you don't have"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn protocol_text_round_trips() {
+    use protocol::{ClientMessage, ServerMessage, Snapshot};
+
+    for line in [
+        "join lunch Ada Lovelace",
+        "rejoin lunch 00000000deadbeef",
+        "leave",
+        "hello 1 json",
+        "say good  luck",
+        "create lunch quick Ada Lovelace",
+        "list",
+        "find casual Bo",
+        "spectate lunch",
+        "ready",
+        "bots",
+        "bid pass",
+        "bid 10",
+        "adjust 0",
+        "concede",
+        "pass 9S JD",
+        "play QH",
+        "deal 42",
+        "over",
+    ]
+    .iter()
+    {
+        let message: ClientMessage = line.parse().unwrap();
+        assert_eq!(message.to_string(), *line);
+    }
+    assert!("bid 12".parse::<ClientMessage>().is_err());
+    assert!("play XX".parse::<ClientMessage>().is_err());
+
+    let game = Tysiac::new(SomeState::deal(&mut stacked_deck().into_iter()));
+    let snapshot = Snapshot::new(&game.view(Player::C).unwrap());
+    assert_eq!(
+        snapshot.to_string(),
+        "Bidding seat=C move=B scores=0,0,0 bid=A:100 trump=- trick= \
+         hand=AC,TC,KC,QC,JC,9C,AD hands=7,7,7"
+    );
+
+    for line in [
+        "seated lunch B 00000000deadbeef",
+        "joined A Ada Lovelace",
+        "left C",
+        "disconnected A",
+        "reconnected A",
+        "event B plays Q♥",
+        "error incorrect-player:B:A It is B's turn, not A's",
+        "error request The table is full",
+        "welcome 1 binary",
+        "chat C good  luck",
+        "tables",
+        "tables lunch quick waiting 1 Ada%20L*,-,@ dinner-1 standard playing 0 Bo,%2D,@",
+        "table lunch casual waiting 0 -,-,-",
+        "state Finished seat=- move=C scores=0,0,0 bid=B:100 trump=- trick= hand= hands=0,0,0",
+        "state Playing seat=A move=B scores=10,-100,0 bid=A:120 trump=H trick=A:QH \
+         hand=KH,9S hands=2,3,3",
+    ]
+    .iter()
+    {
+        let message: ServerMessage = line.parse().unwrap();
+        assert_eq!(message.to_string(), *line);
+    }
+    assert_eq!(
+        ServerMessage::State(snapshot.clone()).to_string().parse(),
+        Ok(ServerMessage::State(snapshot))
+    );
+}
+
+mod loopback {
+    use crate::protocol::{
+        read_frame, write_frame, ClientMessage, Encoding, ErrorKind, ServerMessage, Snapshot, Wire,
+        PROTOCOL_VERSION,
+    };
+    use crate::server::{Hub, Server};
+    use std::io::BufReader;
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::time::Duration;
+    use tysiac::{Player, State};
+
+    pub struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+        encoding: Encoding,
+    }
+
+    impl Client {
+        pub fn connect(addr: SocketAddr) -> Client {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            Client {
+                writer: stream.try_clone().unwrap(),
+                reader: BufReader::new(stream),
+                encoding: Encoding::Text,
+            }
+        }
+
+        /// Sends a message written as text in the client's encoding.
+        pub fn send(&mut self, line: &str) {
+            let message: ClientMessage = line.parse().unwrap();
+            let payload = message.encode(self.encoding);
+            write_frame(&mut self.writer, self.encoding, &payload).unwrap();
+        }
+
+        pub fn recv(&mut self) -> ServerMessage {
+            let payload = read_frame(&mut self.reader, self.encoding)
+                .unwrap()
+                .unwrap();
+            ServerMessage::decode(self.encoding, &payload).unwrap()
+        }
+
+        pub fn hello(&mut self, encoding: Encoding) {
+            self.send(&format!("hello {} {}", PROTOCOL_VERSION, encoding));
+            assert_eq!(
+                self.recv(),
+                ServerMessage::Welcome {
+                    version: PROTOCOL_VERSION,
+                    encoding
+                }
+            );
+            self.encoding = encoding;
+        }
+
+        /// Skips messages until `f` picks one.
+        pub fn expect<T>(&mut self, mut f: impl FnMut(ServerMessage) -> Option<T>) -> T {
+            loop {
+                if let Some(found) = f(self.recv()) {
+                    return found;
+                }
+            }
+        }
+
+        pub fn snapshot(&mut self) -> Snapshot {
+            self.expect(|message| match message {
+                ServerMessage::State(snapshot) => Some(snapshot),
+                _ => None,
+            })
+        }
+
+        pub fn join(&mut self, table: &str, name: &str) -> (Player, String) {
+            self.send(
+                &ClientMessage::Join {
+                    table: table.to_owned(),
+                    name: name.to_owned(),
+                }
+                .to_string(),
+            );
+            self.expect(|message| match message {
+                ServerMessage::Seated { seat, token, .. } => Some((seat, token)),
+                _ => None,
+            })
+        }
+    }
+
+    pub fn start() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::new(Hub::new(11));
+        std::thread::spawn(move || server.serve(listener));
+        addr
+    }
+
+    #[test]
+    fn tables_over_loopback() {
+        let addr = start();
+        let mut a = Client::connect(addr);
+        let mut b = Client::connect(addr);
+        let mut c = Client::connect(addr);
+        b.hello(Encoding::Json);
+        c.hello(Encoding::Binary);
+
+        a.send("join lunch Ada");
+        assert_eq!(
+            a.recv(),
+            ServerMessage::request_error("There is no such table")
+        );
+        a.send("create lunch standard Ada");
+        let seat = a.expect(|m| match m {
+            ServerMessage::Seated { seat, .. } => Some(seat),
+            _ => None,
+        });
+        assert_eq!(seat, Player::A);
+
+        // A line break in a name would start a new text frame for others
+        let sneaky = ClientMessage::Join {
+            table: "lunch".to_owned(),
+            name: "Bo\nchat A hi".to_owned(),
+        };
+        write_frame(&mut b.writer, b.encoding, &sneaky.encode(b.encoding)).unwrap();
+        assert_eq!(
+            b.recv(),
+            ServerMessage::request_error("Names cannot be empty or hold control characters")
+        );
+
+        assert_eq!(b.join("lunch", "Bo").0, Player::B);
+        let (seat, token) = c.join("lunch", "Cy");
+        assert_eq!(seat, Player::C);
+        a.send("ready");
+        b.send("ready");
+        c.send("ready");
+
+        let seen_by_a = a.snapshot();
+        let seen_by_c = c.snapshot();
+        assert_eq!(seen_by_a.state, State::Bidding);
+        assert_eq!(seen_by_a.hand_sizes, [7, 7, 7]);
+        assert_eq!(seen_by_a.hand.len(), 7);
+        assert!(seen_by_a
+            .hand
+            .iter()
+            .all(|card| !seen_by_c.hand.contains(card)));
+
+        let mut d = Client::connect(addr);
+        d.send("join lunch Di");
+        assert_eq!(d.recv(), ServerMessage::request_error("The table is full"));
+        d.send("hello 99 json");
+        match d.recv() {
+            ServerMessage::Error { kind, .. } => assert_eq!(
+                kind,
+                ErrorKind::Version {
+                    supported: PROTOCOL_VERSION
+                }
+            ),
+            message => panic!("Expected a version error, got {:?}", message),
+        }
+
+        a.send("bid pass");
+        assert_eq!(
+            a.recv(),
+            ServerMessage::Error {
+                kind: ErrorKind::IncorrectPlayer {
+                    current: Player::B,
+                    attempted: Some(Player::A)
+                },
+                message: "It is B's turn, not A's".to_owned()
+            }
+        );
+        b.send("say good luck");
+        let chat = ServerMessage::Chat {
+            seat: Player::B,
+            text: "good luck".to_owned(),
+        };
+        a.expect(|m| Some(m).filter(|m| *m == chat));
+        b.send("bid pass");
+        a.expect(|m| Some(m).filter(|m| *m == ServerMessage::Event("B passes".to_owned())));
+
+        drop(c);
+        a.expect(|m| Some(m).filter(|m| *m == ServerMessage::Disconnected { seat: Player::C }));
+        // A bot plays for C meanwhile, so A and B are not left waiting
+        a.expect(|m| Some(m).filter(|m| *m == ServerMessage::Event("C passes".to_owned())));
+        let snapshot = a.snapshot();
+        assert_eq!(snapshot.state, State::AdjustingBid);
+        assert_eq!(snapshot.hand.len(), 10);
+        assert_eq!(snapshot.hand_sizes, [10, 7, 7]);
+
+        let mut c = Client::connect(addr);
+        c.hello(Encoding::Binary);
+        c.send(&format!("rejoin lunch {}", token));
+        let seat = c.expect(|m| match m {
+            ServerMessage::Seated { seat, .. } => Some(seat),
+            _ => None,
+        });
+        assert_eq!(seat, Player::C);
+        a.expect(|m| Some(m).filter(|m| *m == ServerMessage::Reconnected { seat: Player::C }));
+        assert_eq!(c.snapshot().hand, seen_by_c.hand);
+    }
+
+    #[test]
+    fn tables_over_websocket() {
+        use tungstenite::{Message, WebSocket};
+
+        let server = Server::new(Hub::new(11));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let ws_addr = listener.local_addr().unwrap();
+        let ws_server = server.clone();
+        std::thread::spawn(move || ws_server.serve_websocket(listener));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || server.serve(listener));
+
+        fn recv(socket: &mut WebSocket<TcpStream>) -> Message {
+            loop {
+                match socket.read().unwrap() {
+                    message @ Message::Text(_) | message @ Message::Binary(_) => return message,
+                    _ => {}
+                }
+            }
+        }
+
+        let stream = TcpStream::connect(ws_addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let (mut ada, _) = tungstenite::client(format!("ws://{}/", ws_addr), stream).unwrap();
+        ada.send(Message::text("create supper quick Ada")).unwrap();
+        let seated = recv(&mut ada).into_data();
+        assert!(matches!(
+            ServerMessage::decode(Encoding::Text, &seated),
+            Ok(ServerMessage::Seated {
+                seat: Player::A,
+                ..
+            })
+        ));
+
+        // Both transports seat players at the same tables
+        let mut bo = Client::connect(addr);
+        assert_eq!(bo.join("supper", "Bo").0, Player::B);
+        let joined = ServerMessage::Joined {
+            seat: Player::B,
+            name: "Bo".to_owned(),
+        };
+        let text = |ada: &mut WebSocket<TcpStream>| {
+            ServerMessage::decode(Encoding::Text, &recv(ada).into_data()).unwrap()
+        };
+        while text(&mut ada) != joined {}
+
+        ada.send(Message::text("hello 2 binary")).unwrap();
+        let welcome = ServerMessage::Welcome {
+            version: PROTOCOL_VERSION,
+            encoding: Encoding::Binary,
+        };
+        while text(&mut ada) != welcome {}
+        ada.send(Message::binary(
+            ClientMessage::List.encode(Encoding::Binary),
+        ))
+        .unwrap();
+        loop {
+            match recv(&mut ada) {
+                Message::Binary(payload) => {
+                    match ServerMessage::decode(Encoding::Binary, &payload) {
+                        Ok(ServerMessage::Tables(tables)) => {
+                            assert_eq!(tables[0].name, "supper");
+                            break;
+                        }
+                        message => assert!(message.is_ok(), "{:?}", message),
+                    }
+                }
+                message => panic!("Expected a binary message, got {:?}", message),
+            }
+        }
+    }
+}
+
+#[test]
+fn protocol_conformance() {
+    use protocol::{ClientMessage, Encoding, ServerMessage, Wire};
+
+    fn check<M: Wire + std::fmt::Debug + PartialEq>(lines: &[(&str, &str)]) {
+        let message = M::decode(Encoding::Text, lines[0].1.as_bytes()).unwrap();
+        for (encoding, payload) in lines {
+            let encoding: Encoding = encoding.parse().unwrap();
+            let payload = bytes(encoding, payload);
+            assert_eq!(
+                message.encode(encoding),
+                payload,
+                "{:?} as {}",
+                message,
+                encoding
+            );
+            assert_eq!(M::decode(encoding, &payload).as_ref(), Ok(&message));
+        }
+    }
+
+    fn bytes(encoding: Encoding, payload: &str) -> Vec<u8> {
+        match encoding {
+            Encoding::Binary => payload
+                .split_whitespace()
+                .map(|b| u8::from_str_radix(b, 16).unwrap())
+                .collect(),
+            _ => payload.as_bytes().to_vec(),
+        }
+    }
+
+    let vectors = include_str!("protocol/conformance.txt");
+    let mut checked = 0;
+    for block in vectors.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.starts_with('#')).collect();
+        match lines.as_slice() {
+            [] => {}
+            ["client", encodings @ ..] | ["server", encodings @ ..] => {
+                let encodings: Vec<(&str, &str)> = encodings
+                    .iter()
+                    .map(|line| line.split_once(' ').unwrap())
+                    .collect();
+                assert_eq!(encodings.len(), 3, "{}", block);
+                if lines[0] == "client" {
+                    check::<ClientMessage>(&encodings);
+                } else {
+                    check::<ServerMessage>(&encodings);
+                }
+                checked += 1;
+            }
+            invalid => {
+                for line in invalid {
+                    let mut words = line.splitn(4, ' ');
+                    assert_eq!(words.next(), Some("invalid"));
+                    let sender = words.next().unwrap();
+                    let encoding: Encoding = words.next().unwrap().parse().unwrap();
+                    let payload = bytes(encoding, words.next().unwrap());
+                    let refused = match sender {
+                        "client" => ClientMessage::decode(encoding, &payload).is_err(),
+                        _ => ServerMessage::decode(encoding, &payload).is_err(),
+                    };
+                    assert!(refused, "{}", line);
+                    checked += 1;
+                }
+            }
+        }
+    }
+    assert_eq!(checked, 65);
+}
+
+#[test]
+fn protocol_refuses_deep_nesting_and_long_lines() {
+    use protocol::{read_frame, ClientMessage, Encoding, Wire, MAX_FRAME};
+    use std::io::Cursor;
+
+    let deep = "[".repeat(100_000);
+    assert!(ClientMessage::decode(Encoding::Json, deep.as_bytes()).is_err());
+
+    let mut longest = vec![b'a'; MAX_FRAME];
+    longest.extend_from_slice(b"\r\n");
+    let frame = read_frame(&mut Cursor::new(longest), Encoding::Text).unwrap();
+    assert_eq!(frame.map(|line| line.len()), Some(MAX_FRAME));
+
+    let too_long = vec![b'a'; MAX_FRAME + 1];
+    assert!(read_frame(&mut Cursor::new(too_long), Encoding::Json).is_err());
+}
+
+#[test]
+fn json_moves_say_why_they_are_refused() {
+    use protocol::{ClientMessage, DecodeError, Encoding, Wire};
+
+    let decode = |json: &str| ClientMessage::decode(Encoding::Json, json.as_bytes());
+    assert_eq!(
+        decode(r#"{"type":"bid","raise":12}"#),
+        Err(DecodeError("12 is not a multiple of five".to_owned()))
+    );
+    assert_eq!(
+        decode(r#"{"type":"play","card":"ZZ"}"#),
+        Err(DecodeError("\"ZZ\" is not a card".to_owned()))
+    );
+}
+
+#[test]
+fn lobby_fills_starts_and_finishes_tables() {
+    use protocol::{ClientMessage, Preset, SeatInfo, ServerMessage};
+    use server::Hub;
+    use std::sync::mpsc::{channel, Receiver};
+
+    fn drain(receiver: &Receiver<ServerMessage>) -> Vec<ServerMessage> {
+        receiver.try_iter().collect()
+    }
+    fn send(hub: &mut Hub, connection: usize, line: &str) {
+        hub.handle(connection, line.parse::<ClientMessage>().unwrap());
+    }
+
+    let mut hub = Hub::new(5);
+    let (ann_sender, ann) = channel();
+    let (sam_sender, sam) = channel();
+    let (pat_sender, pat) = channel();
+    let ann_id = hub.connect(ann_sender);
+    let sam_id = hub.connect(sam_sender);
+    let pat_id = hub.connect(pat_sender);
+
+    send(&mut hub, ann_id, "create lunch quick Ann");
+    send(&mut hub, sam_id, "spectate lunch");
+    send(&mut hub, ann_id, "ready");
+    let info = hub.tables().remove(0);
+    assert_eq!(info.preset, Preset::Quick);
+    assert_eq!(info.spectators, 1);
+    assert!(!info.started);
+    assert_eq!(
+        info.seats[0],
+        SeatInfo::Human {
+            name: "Ann".to_owned(),
+            ready: true
+        }
+    );
+
+    send(&mut hub, ann_id, "bots");
+    let info = hub.tables().remove(0);
+    assert!(info.started);
+    assert_eq!(info.seats[1], SeatInfo::Bot);
+    assert!(drain(&ann).iter().any(
+        |m| matches!(m, ServerMessage::State(s) if s.seat == Some(Player::A) && !s.hand.is_empty())
+    ));
+    let watched = drain(&sam);
+    assert!(watched.contains(&ServerMessage::Event("The match starts".to_owned())));
+    assert!(watched
+        .iter()
+        .any(|m| matches!(m, ServerMessage::State(s) if s.seat.is_none() && s.hand.is_empty())));
+
+    send(&mut hub, pat_id, "list");
+    assert!(matches!(drain(&pat).as_slice(), [ServerMessage::Tables(tables)] if tables[0].started));
+    send(&mut hub, ann_id, "say hi");
+    send(&mut hub, sam_id, "ready");
+    assert_eq!(
+        drain(&sam),
+        [
+            ServerMessage::Chat {
+                seat: Player::A,
+                text: "hi".to_owned()
+            },
+            ServerMessage::request_error("Spectators only watch")
+        ]
+    );
+
+    // The bots play the match out once Ann gives up her seat
+    send(&mut hub, ann_id, "leave");
+    let watched = drain(&sam);
+    assert_eq!(watched[0], ServerMessage::Left { seat: Player::A });
+    assert!(watched.iter().any(
+        |m| matches!(m, ServerMessage::Event(e) if e.ends_with("wins the match after 5 hands"))
+    ));
+    let info = hub.tables().remove(0);
+    assert!(!info.started);
+    assert_eq!(info.seats, [SeatInfo::Bot, SeatInfo::Bot, SeatInfo::Bot]);
+
+    send(&mut hub, pat_id, "find quick Pat");
+    match drain(&pat).first() {
+        Some(ServerMessage::Seated { table, seat, .. }) => {
+            assert_eq!((table.as_str(), *seat), ("quick-1", Player::A))
+        }
+        message => panic!("Expected a seat, got {:?}", message),
+    }
+
+    // Nobody is left to watch the bots
+    hub.disconnect(sam_id);
+    let names: Vec<String> = hub.tables().into_iter().map(|t| t.name).collect();
+    assert_eq!(names, ["quick-1"]);
+
+    // Pat's seat is given up as the match has not started
+    hub.disconnect(pat_id);
+    assert!(hub.tables().is_empty());
+}
@@ -1,26 +1,39 @@
-use card_games_lib::{game_states, pile, pile_extract, step_try, Pile, Step, StepResult};
-use core::convert::TryFrom;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use card_games_lib::{
+    game_states, pile_extract, step_try, CardSet, Deck, Describe, ExtractError, ParseError, Pile,
+    Seat, Seats, StepResult, TakenTricks, Trick, TrickError, TrickRules, TysiacOrder,
+};
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
 use core::ops::Add;
-use itertools::Itertools;
-use rand::{seq::SliceRandom, Rng};
-use std::convert::TryInto;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
+use core::str::FromStr;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use ranged_num::define_ranged_enum;
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Game {
-    points: [isize; 3],
+    points: Seats<Player, isize>,
+    dealer: Player,
 }
 
 impl Default for Game {
     fn default() -> Game {
-        Game { points: [0; 3] }
+        Game {
+            points: Seats::default(),
+            dealer: FIRST_DEALER,
+        }
     }
 }
 
 impl Game {
     fn add_points(&mut self, player: Player, num: isize, is_bidder: bool) {
-        let points = &mut self.points[player.index()];
+        let points = &mut self.points[player];
         *points += num;
         if *points > 880 {
             if is_bidder && *points > 1000 {
@@ -32,22 +45,35 @@ impl Game {
     }
 
     pub fn points(&self, player: Player) -> isize {
-        self.points[player.index()]
+        self.points[player]
     }
-}
-
-define_ranged_enum!(Player, Derive(EnumIter, Clone, Copy, Debug, PartialEq, Eq), A, B, C);
 
-struct InfinitePlayerIter(Player);
-
-impl Iterator for InfinitePlayerIter {
-    type Item = Player;
-    fn next(&mut self) -> std::option::Option<<Self as std::iter::Iterator>::Item> {
-        self.0 = self.0.next();
-        Some(self.0)
+    /// Who dealt the hand being played. The player after the dealer opens
+    /// the bidding at 100.
+    pub fn dealer(&self) -> Player {
+        self.dealer
     }
 }
 
+define_ranged_enum!(
+    Player,
+    Derive(Clone, Copy, Debug, PartialEq, Eq, Hash),
+    A,
+    B,
+    C
+);
+
+/// The dealer of the first hand, so that `A` opens the bidding.
+const FIRST_DEALER: Player = Player::C;
+
+/// Tysiac only requires following suit.
+pub const TRICK_RULES: TrickRules<TysiacOrder> = TrickRules {
+    order: TysiacOrder,
+    must_follow: true,
+    must_trump: false,
+    must_overtake: false,
+};
+
 impl SomeState {
     pub fn random<R>(rng: &mut R) -> Self
     where
@@ -62,13 +88,94 @@ impl SomeState {
             SomeState::AdjustingBid(x) => x.bid_winner,
             SomeState::Distrubuting(x) => x.bid_winner,
             SomeState::Playing(x) => x.player,
-            SomeState::Finished(_) => Player::A,
+            SomeState::Finished(x) => x.dealer,
+            SomeState::GameOver(x) => x.winner,
         }
     }
 
     pub fn deal(deck: &mut impl Iterator<Item = Card>) -> Self {
         SomeState::Bidding(Bidding::deal(deck))
     }
+
+    pub fn hand(&self, player: &Player) -> Option<&Pile<Card>> {
+        match self {
+            SomeState::Bidding(x) => Some(&x.hands[*player]),
+            SomeState::AdjustingBid(x) => Some(&x.hands[*player]),
+            SomeState::Distrubuting(x) => Some(&x.hands[*player]),
+            SomeState::Playing(x) => Some(&x.hands[*player]),
+            SomeState::Finished(_) | SomeState::GameOver(_) => None,
+        }
+    }
+
+    /// The highest bid so far and who made it, or the winning bid once
+    /// bidding is over.
+    pub fn bid(&self) -> Option<(Player, usize)> {
+        match self {
+            SomeState::Bidding(x) => Some((x.current_bid.0, x.current_bid.1.into())),
+            SomeState::AdjustingBid(x) => Some((x.bid_winner, x.bid.into())),
+            SomeState::Distrubuting(x) => Some((x.bid_winner, x.bid)),
+            SomeState::Playing(x) => Some((x.bid_winner, x.bid)),
+            SomeState::Finished(x) => Some((x.bid_winner, x.bid)),
+            SomeState::GameOver(_) => None,
+        }
+    }
+
+    pub fn trick(&self) -> Option<&Trick<Player, Card>> {
+        match self {
+            SomeState::Playing(x) => Some(&x.trick),
+            _ => None,
+        }
+    }
+
+    pub fn trump(&self) -> Option<&Suit> {
+        match self {
+            SomeState::Playing(x) => x.trump.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The card points the bid winner has taken and the marriages they have
+    /// declared, which together must reach the bid.
+    pub fn bidder_score(&self) -> Option<isize> {
+        let (bid_winner, taken, pending_points) = match self {
+            SomeState::Playing(x) => (x.bid_winner, &x.taken, x.pending_points),
+            SomeState::Finished(x) => (x.bid_winner, &x.taken, x.pending_points),
+            _ => return None,
+        };
+        let points: isize = taken
+            .cards_won_by(bid_winner)
+            .map(|c| isize::from(c.rank().point_value()))
+            .sum();
+
+        Some(points + pending_points)
+    }
+
+    /// The prikup, which everyone sees when the bid winner picks it up.
+    pub fn prikup(&self) -> Option<CardSet> {
+        match self {
+            SomeState::AdjustingBid(x) => Some(x.prikup),
+            SomeState::Distrubuting(x) => Some(x.prikup),
+            SomeState::Playing(x) => Some(x.prikup),
+            _ => None,
+        }
+    }
+
+    /// The card the bid winner passed to each player. Only the bid winner
+    /// knows both.
+    pub fn passed(&self) -> Option<&Seats<Player, CardSet>> {
+        match self {
+            SomeState::Playing(x) => Some(&x.passed),
+            _ => None,
+        }
+    }
+
+    pub fn taken(&self) -> Option<&TakenTricks<Player, Card>> {
+        match self {
+            SomeState::Playing(x) => Some(&x.taken),
+            SomeState::Finished(x) => Some(&x.taken),
+            _ => None,
+        }
+    }
 }
 
 impl Bidding {
@@ -76,18 +183,31 @@ impl Bidding {
     where
         R: Rng,
     {
-        let mut deck: Vec<_> = all_cards().collect();
-        deck.shuffle(rng);
+        Self::shuffled_by(FIRST_DEALER, rng)
+    }
+
+    pub fn deal(deck: &mut impl Iterator<Item = Card>) -> Self {
+        Self::dealt_by(FIRST_DEALER, deck)
+    }
 
-        let mut deck = deck.drain(..);
+    pub fn shuffled_by<R>(dealer: Player, rng: &mut R) -> Self
+    where
+        R: Rng,
+    {
+        let mut deck = Deck::schnapsen().map(|c| {
+            Card::from_description(c).expect("The schnapsen deck only holds tysiac cards")
+        });
+        deck.shuffle(rng);
 
-        Self::deal(&mut deck)
+        Self::dealt_by(dealer, &mut deck.into_iter())
     }
 
-    pub fn deal(deck: &mut impl Iterator<Item = Card>) -> Self {
+    /// Deals a hand where the player after `dealer` is forced to open at 100.
+    pub fn dealt_by(dealer: Player, deck: &mut impl Iterator<Item = Card>) -> Self {
+        let opener = dealer.next();
         Self {
-            hands: Piles::deal(deck),
-            current_bid: (Player::A, Fives::one_hundred(), Player::B),
+            hands: Seats::new(|_| Pile::deal(deck, 7)),
+            current_bid: (opener, Fives::one_hundred(), opener.next()),
             prikup: [
                 deck.next().unwrap(),
                 deck.next().unwrap(),
@@ -99,11 +219,33 @@ impl Bidding {
 
 impl Distrubuting {
     pub fn hand(&self, player: &Player) -> &Pile<Card> {
-        self.hands.hand(player)
+        &self.hands[*player]
     }
 }
 
-#[derive(EnumIter, Clone, Debug, PartialEq, Eq)]
+impl Playing {
+    pub fn hand(&self, player: &Player) -> &Pile<Card> {
+        &self.hands[*player]
+    }
+
+    /// A copy of the state with the hands replaced, e.g. by hands sampled
+    /// for a search.
+    pub fn with_hands(&self, hands: Piles) -> Playing {
+        Playing {
+            hands,
+            ..self.clone()
+        }
+    }
+
+    /// The cards the player to move may play.
+    pub fn legal_cards(&self) -> CardSet {
+        let hand = CardSet::from(&self.hands[self.player]);
+        let trump = self.trump.as_ref().map(Suit::description);
+        TRICK_RULES.legal_plays(&self.trick, trump, hand)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Suit {
     Spades,
     Clubs,
@@ -111,7 +253,7 @@ pub enum Suit {
     Hearts,
 }
 
-#[derive(EnumIter, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Rank {
     Nine,
     Jack,
@@ -121,14 +263,9 @@ pub enum Rank {
     Ace,
 }
 
-fn all_cards() -> impl Iterator<Item = Card> {
-    Rank::iter()
-        .cartesian_product(Suit::iter())
-        .map(|(r, s)| Card(r, s))
-}
-
-#[derive(Debug, PartialEq, Eq)]
-// note that you cannot copy cards as these represent the physical deck
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+// note that cards are not Copy as these represent the physical deck; cloning
+// is only for snapshots of a whole game
 pub struct Card(Rank, Suit);
 
 impl PartialEq<Card> for card_games_lib::Card {
@@ -137,6 +274,12 @@ impl PartialEq<Card> for card_games_lib::Card {
     }
 }
 
+impl Describe for Card {
+    fn description(&self) -> card_games_lib::Card {
+        Card::description(self)
+    }
+}
+
 impl PartialEq<card_games_lib::Suit> for &Suit {
     fn eq(&self, other: &card_games_lib::Suit) -> bool {
         self.description() == *other
@@ -161,9 +304,64 @@ impl Card {
     pub fn description(&self) -> card_games_lib::Card {
         card_games_lib::Card(self.rank().description(), self.suit().description())
     }
+
+    /// The card's Polish name, such as "Dama kier".
+    pub fn polish_name(&self) -> String {
+        format!(
+            "{} {}",
+            self.rank().polish_name(),
+            self.suit().polish_name().to_lowercase()
+        )
+    }
+
+    pub fn from_description(card: card_games_lib::Card) -> Option<Card> {
+        Some(Card(
+            Rank::from_description(card.rank())?,
+            Suit::from_description(card.suit())?,
+        ))
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+/// Accepts any notation `card_games_lib::Card` can parse, as well as the
+/// Polish names given by `Card::polish_name`.
+impl FromStr for Card {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Card, ParseError> {
+        let polish = || {
+            let mut words = s.split_whitespace();
+            let rank = words.next()?;
+            let suit = words.next()?;
+            if words.next().is_some() {
+                return None;
+            }
+
+            let rank = Rank::ALL
+                .iter()
+                .find(|r| r.polish_name().to_lowercase() == rank.to_lowercase())?;
+            let suit = Suit::ALL
+                .iter()
+                .find(|s| s.polish_name().to_lowercase() == suit.to_lowercase())?;
+            Some(Card(rank.clone(), suit.clone()))
+        };
+
+        match s.parse::<card_games_lib::Card>() {
+            Ok(card) => Card::from_description(card),
+            Err(_) => polish(),
+        }
+        .ok_or_else(|| ParseError::UnknownCard(s.trim().to_owned()))
+    }
 }
 
 impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Spades, Suit::Clubs, Suit::Diamonds, Suit::Hearts];
+
     pub fn marriage_value(&self) -> u8 {
         match self {
             Suit::Hearts => 100,
@@ -181,9 +379,36 @@ impl Suit {
             Suit::Spades => card_games_lib::Suit::Spades,
         }
     }
+
+    pub fn from_description(suit: card_games_lib::Suit) -> Option<Suit> {
+        match suit {
+            card_games_lib::Suit::Diamonds => Some(Suit::Diamonds),
+            card_games_lib::Suit::Clubs => Some(Suit::Clubs),
+            card_games_lib::Suit::Hearts => Some(Suit::Hearts),
+            card_games_lib::Suit::Spades => Some(Suit::Spades),
+        }
+    }
+
+    pub fn polish_name(&self) -> &'static str {
+        match self {
+            Suit::Hearts => "Kier",
+            Suit::Diamonds => "Karo",
+            Suit::Clubs => "Trefl",
+            Suit::Spades => "Pik",
+        }
+    }
 }
 
 impl Rank {
+    pub const ALL: [Rank; 6] = [
+        Rank::Nine,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ten,
+        Rank::Ace,
+    ];
+
     pub fn point_value(&self) -> u8 {
         match self {
             Rank::Ace => 11,
@@ -209,21 +434,37 @@ impl Rank {
             Rank::Nine => card_games_lib::Rank::Nine,
         }
     }
-}
 
-#[derive(Debug, PartialEq, Eq)]
-struct Piles([Pile<Card>; 3]);
+    pub fn from_description(rank: card_games_lib::Rank) -> Option<Rank> {
+        match rank {
+            card_games_lib::Rank::Ace => Some(Rank::Ace),
+            card_games_lib::Rank::Ten => Some(Rank::Ten),
+            card_games_lib::Rank::King => Some(Rank::King),
+            card_games_lib::Rank::Queen => Some(Rank::Queen),
+            card_games_lib::Rank::Jack => Some(Rank::Jack),
+            card_games_lib::Rank::Nine => Some(Rank::Nine),
+            _ => None,
+        }
+    }
 
-impl Player {
-    pub fn next(&self) -> Self {
+    pub fn polish_name(&self) -> &'static str {
         match self {
-            Player::A => Player::B,
-            Player::B => Player::C,
-            Player::C => Player::A,
+            Rank::Ace => "As",
+            Rank::Ten => "Dziesiątka",
+            Rank::King => "Król",
+            Rank::Queen => "Dama",
+            Rank::Jack => "Walet",
+            Rank::Nine => "Dziewiątka",
         }
     }
+}
 
-    pub fn from_index(index: usize) -> Option<Player> {
+pub type Piles = Seats<Player, Pile<Card>>;
+
+impl Seat for Player {
+    const COUNT: usize = 3;
+
+    fn from_index(index: usize) -> Option<Player> {
         match index {
             0 => Some(Player::A),
             1 => Some(Player::B),
@@ -232,7 +473,7 @@ impl Player {
         }
     }
 
-    pub fn index(&self) -> usize {
+    fn index(self) -> usize {
         match self {
             Player::A => 0,
             Player::B => 1,
@@ -241,29 +482,7 @@ impl Player {
     }
 }
 
-impl Piles {
-    fn empty() -> Piles {
-        Piles([pile!(), pile!(), pile!()])
-    }
-
-    fn deal(deck: &mut impl Iterator<Item = Card>) -> Piles {
-        Piles([
-            Pile::deal(deck, 7),
-            Pile::deal(deck, 7),
-            Pile::deal(deck, 7),
-        ])
-    }
-
-    fn hand(&self, p: &Player) -> &Pile<Card> {
-        &self.0[p.index()]
-    }
-
-    fn hand_mut(&mut self, p: &Player) -> &mut Pile<Card> {
-        &mut self.0[p.index()]
-    }
-}
-
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Fives(usize);
 
 impl Fives {
@@ -320,7 +539,18 @@ impl Add<Fives> for Fives {
 
 game_states! {
     context: Game,
+    attributes: {
+        SomeState: #[derive(Clone, PartialEq, Eq, Hash)],
+        StateInput: #[derive(Clone, PartialEq, Eq, Hash)],
+        StateError: #[derive(Clone, Hash)],
+    },
+    branches: {
+        /// The next hand, or the end of the game once a player reaches 1000.
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        FinishedNext { Bidding, GameOver },
+    },
     states: {
+        #[derive(Clone, PartialEq, Eq, Hash)]
         Bidding {
             hands: Piles,
             prikup: [Card; 3],
@@ -345,10 +575,11 @@ game_states! {
                         hands: this.hands,
                     })
                 } else {
+                    let prikup = this.prikup.iter().map(Card::description).collect();
                     let [pick_1, pick_2, pick_3] = this.prikup;
                     let mut hands = this.hands;
 
-                    let highest_bidders_hand = hands.hand_mut(&highest_bidder);
+                    let highest_bidders_hand = &mut hands[highest_bidder];
                     highest_bidders_hand.add(pick_1);
                     highest_bidders_hand.add(pick_2);
                     highest_bidders_hand.add(pick_3);
@@ -357,81 +588,119 @@ game_states! {
                         bid_winner: highest_bidder,
                         bid: current_bid,
                         hands: hands,
+                        prikup,
                     })
                 }
             }
         },
+        #[derive(Clone, PartialEq, Eq, Hash)]
         AdjustingBid {
             bid_winner: Player,
             bid: Fives,
-            hands: Piles
+            hands: Piles,
+            prikup: CardSet
         } (increase: Fives) -> ( Distrubuting , String ) |this, _context, increase| {
             let new_bid = step_try!(this.bid + increase, this, "Bid increase is too high".to_owned());
             StepResult::cont(Distrubuting {
                 bid_winner: this.bid_winner,
                 hands: this.hands,
                 bid: new_bid.into(),
+                prikup: this.prikup,
+            })
+        };
+        action Concede() -> ( Finished, String ) |this, context| {
+            // The defenders are paid the card points they hold, as though
+            // they had taken them in play
+            for (player, hand) in this.hands.iter() {
+                if player != this.bid_winner {
+                    let points = hand.iter().map(|c| isize::from(c.rank().point_value())).sum();
+                    context.add_points(player, points, false);
+                }
+            }
+
+            StepResult::cont(Finished {
+                bid_winner: this.bid_winner,
+                taken: TakenTricks::default(),
+                pending_points: 0,
+                bid: this.bid.into(),
+                dealer: context.dealer.next(),
             })
         },
+        #[derive(Clone, PartialEq, Eq, Hash)]
         Distrubuting {
             bid_winner: Player,
             hands: Piles,
-            bid: usize
+            bid: usize,
+            prikup: CardSet
         } (next: card_games_lib::Card, prev: card_games_lib::Card) -> ( Playing, String ) |mut this, _context, card_for_next, card_for_prev| {
-            let (card_for_next, card_for_prev) = step_try!(
-                pile_extract!(this.hands.hand_mut(&this.bid_winner), card_for_next, card_for_prev)
-                , this, "Trying to pass a card you don't have".to_owned());
+            let extracted = pile_extract!(try &mut this.hands[this.bid_winner], card_for_next, card_for_prev);
+            let (card_for_next, card_for_prev) = match extracted {
+                Ok(cards) => cards,
+                Err(ExtractError::Missing { description, .. }) => {
+                    return StepResult::fail(this, format!("Trying to pass {:?}, which you don't have", description))
+                }
+                Err(ExtractError::Duplicate { description, .. }) => {
+                    return StepResult::fail(this, format!("Trying to pass {:?} twice", description))
+                }
+            };
+
+            let mut passed: Seats<Player, CardSet> = Seats::default();
 
             let next_player = this.bid_winner.next();
-            this.hands.hand_mut(&next_player).add(card_for_next);
+            passed[next_player].insert(card_for_next.description());
+            this.hands[next_player].add(card_for_next);
 
             let next_player = next_player.next();
-            this.hands.hand_mut(&next_player).add(card_for_prev);
+            passed[next_player].insert(card_for_prev.description());
+            this.hands[next_player].add(card_for_prev);
 
             StepResult::cont(Playing {
                 bid_winner: this.bid_winner,
                 hands: this.hands,
                 trump: None,
-                play_area: pile![],
+                trick: Trick::new(this.bid_winner),
                 player: this.bid_winner,
                 pending_points: 0,
-                taken: Piles::empty(),
+                taken: TakenTricks::default(),
                 bid: this.bid,
+                prikup: this.prikup,
+                passed,
             })
         },
+        #[derive(Clone, PartialEq, Eq, Hash)]
         Playing {
             bid_winner: Player,
             hands: Piles,
-            taken: Piles,
+            taken: TakenTricks<Player, Card>,
             player: Player,
             trump: Option<Suit>,
-            play_area: Pile<Card>,
+            trick: Trick<Player, Card>,
             pending_points: isize,
-            bid: usize
+            bid: usize,
+            prikup: CardSet,
+            passed: Seats<Player, CardSet>
         } (card: card_games_lib::Card) -> ( Finished, String ) |mut this, context, card| {
             let player = this.player;
 
-            if let Some(initial_card) = this.play_area.get(0) {
-                if initial_card.suit() != card.suit() {
-                    let players_hand = this.hands.hand(&player);
+            let players_hand = this.hands[player].iter().map(Card::description);
+            let trump = this.trump.as_ref().map(Suit::description);
 
-                    let any_cards_match_suit = players_hand.iter().filter(|c| &&card != c).any(|c| c.suit() == initial_card.suit());
-
-                    if any_cards_match_suit {
-                        let message = format!("Cannot play card of {:?} when have {:?} in hand", card.suit(), initial_card.suit());
-                        return StepResult::fail(this, message)
-                    }
-                }
+            if let Err(error) = TRICK_RULES.check(&this.trick, trump, players_hand, card) {
+                let message = match error {
+                    TrickError::MustFollow(lead) => format!("Cannot play card of {:?} when have {:?} in hand", card.suit(), lead),
+                    error => format!("{:?}", error),
+                };
+                return StepResult::fail(this, message)
             }
 
-            let played_card = step_try!(this.hands.hand_mut(&player).remove(&card), this, format!("{:?} is not in hand", card));
-            let mut play_area = this.play_area;
+            let played_card = step_try!(this.hands[player].remove(&card), this, format!("{:?} is not in hand", card));
+            let mut trick = this.trick;
             let mut next_player = player.next();
             let mut trump = this.trump;
             let mut pending_points = this.pending_points;
 
-            if play_area.len() == 0 && played_card.rank().is_weddable() {
-                let has_marriage = this.hands.hand(&player).iter().any(|c| c.suit() == played_card.suit() && c.rank().is_weddable());
+            if trick.is_empty() && played_card.rank().is_weddable() {
+                let has_marriage = this.hands[player].iter().any(|c| c.suit() == played_card.suit() && c.rank().is_weddable());
                 if has_marriage {
                     trump = Some(played_card.suit().clone());
 
@@ -443,31 +712,19 @@ game_states! {
                 }
             }
 
-            play_area.add(played_card);
+            trick.play(player, played_card);
 
             let mut game_over = false;
 
-            if play_area.len() == 3 {
-                let lead_suit = play_area.get(0).expect("There is at least 1 card").suit();
-
-                let winning_card = trump.iter()
-                                        .flat_map(|trump| play_area.iter().filter(move |c| c.suit() == trump))
-                                        .max_by_key(|c|c.rank())
-                                        .or_else(|| play_area.iter().filter(move |c| c.suit() == lead_suit).max_by_key(|c|c.rank()))
-                                        .expect("There will be a highest card of lead suit");
-
-
-                let winner = play_area.iter().zip(InfinitePlayerIter(next_player))
-                                            .filter(|(card, _)| card == &winning_card)
-                                            .map(|(_, player)| player)
-                                            .next()
-                                            .expect("Some card won");
+            if trick.len() == 3 {
+                let winner = trick.winner(TysiacOrder, trump.as_ref().map(Suit::description)).expect("Some card won");
 
                 next_player = winner;
 
-                this.taken.hand_mut(&winner).extend(play_area.drain());
+                this.taken.record(winner, trick);
+                trick = Trick::new(winner);
 
-                game_over = this.hands.hand(&Player::A).len() == 0;
+                game_over = this.hands[Player::A].is_empty();
             }
 
             if !game_over {
@@ -477,42 +734,62 @@ game_states! {
                     trump: trump,
                     pending_points: pending_points,
                     player: next_player,
-                    play_area: play_area,
+                    trick,
                     taken: this.taken,
                     bid: this.bid,
+                    prikup: this.prikup,
+                    passed: this.passed,
                 })
             } else {
-                for player in Player::iter() {
-                    let taken = this.taken.hand(&player);
-                    let score = taken.iter().map(|c| isize::from(c.rank().point_value())).sum();
-
-                    if player == this.bid_winner {
-                        let bid : isize = this.bid.try_into().unwrap();
-
-                        let delta = if score + pending_points < bid {
-                            - bid
-                        } else {
-                            bid
-                        };
-
-                        context.add_points(player, delta, true);
-                    } else {
-                        context.add_points(player, score, false);
-                    }
-                }
-
                 StepResult::cont(Finished {
                     bid_winner: this.bid_winner,
                     taken: this.taken,
+                    pending_points,
                     bid: this.bid,
+                    dealer: context.dealer.next(),
                 })
             }
         },
+        #[derive(Clone, PartialEq, Eq, Hash)]
         Finished {
             bid_winner: Player,
-            taken: Piles,
-            bid: usize
-        } () -> (Finished, String) |this, _context| {
+            taken: TakenTricks<Player, Card>,
+            pending_points: isize,
+            bid: usize,
+            dealer: Player
+        } (seed: u64) -> (FinishedNext, String) |this, context, seed| {
+            match Player::all().find(|player| context.points(*player) >= 1000) {
+                Some(winner) => StepResult::cont(GameOver { winner }.into()),
+                None => {
+                    context.dealer = this.dealer;
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    StepResult::cont(Bidding::shuffled_by(this.dealer, &mut rng).into())
+                }
+            }
+        };
+        on_enter: |this, context| {
+            for player in Player::all() {
+                let score = this.taken.cards_won_by(player).map(|c| isize::from(c.rank().point_value())).sum();
+
+                if player == this.bid_winner {
+                    let bid : isize = this.bid.try_into().unwrap();
+
+                    let delta = if score + this.pending_points < bid {
+                        - bid
+                    } else {
+                        bid
+                    };
+
+                    context.add_points(player, delta, true);
+                } else {
+                    context.add_points(player, score, false);
+                }
+            }
+        },
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        GameOver {
+            winner: Player,
+        } () -> (GameOver, String) |this, _context| {
             StepResult::stay(this)
         }
     }
@@ -1,5 +1,5 @@
 use super::*;
-use card_games_lib::{pile, Error, Step};
+use card_games_lib::{pile, Act, Error, Machine, Step};
 use std::convert::TryInto;
 use {Rank::*, Suit::*};
 
@@ -8,7 +8,7 @@ fn test_prikup_1() -> [Card; 3] {
 }
 
 fn test_hands_1() -> Piles {
-    Piles([
+    Seats::from_vec(vec![
         pile![
             Card(Ace, Hearts),
             Card(Ten, Hearts),
@@ -71,7 +71,7 @@ fn bid_a() -> Result<(), Error<String, State>> {
     assert_eq!(state.bid, 130.try_into().unwrap());
     assert_eq!(state.bid_winner, Player::C);
     assert_eq!(
-        state.hands.hand(&Player::C),
+        &state.hands[Player::C],
         &pile![
             Card(Ace, Spades),
             Card(Ten, Spades),
@@ -85,8 +85,8 @@ fn bid_a() -> Result<(), Error<String, State>> {
             Card(Nine, Clubs),
         ]
     );
-    assert_eq!(state.hands.hand(&Player::A).iter().count(), 7);
-    assert_eq!(state.hands.hand(&Player::B).iter().count(), 7);
+    assert_eq!(state.hands[Player::A].iter().count(), 7);
+    assert_eq!(state.hands[Player::B].iter().count(), 7);
 
     let state: Distrubuting = state.step(&mut game, Fives::ten()).next()?;
 
@@ -100,7 +100,7 @@ fn bid_a() -> Result<(), Error<String, State>> {
         .next()?;
 
     assert_eq!(
-        state.hands.hand(&Player::C),
+        &state.hands[Player::C],
         &pile![
             Card(Ace, Spades),
             Card(Ten, Spades),
@@ -113,7 +113,7 @@ fn bid_a() -> Result<(), Error<String, State>> {
         ]
     );
     assert_eq!(
-        state.hands.hand(&Player::A),
+        &state.hands[Player::A],
         &pile![
             Card(Ace, Hearts),
             Card(Ten, Hearts),
@@ -126,7 +126,7 @@ fn bid_a() -> Result<(), Error<String, State>> {
         ]
     );
     assert_eq!(
-        state.hands.hand(&Player::B),
+        &state.hands[Player::B],
         &pile![
             Card(Ace, Diamonds),
             Card(Ten, Diamonds),
@@ -139,5 +139,304 @@ fn bid_a() -> Result<(), Error<String, State>> {
         ]
     );
 
+    let set = |cards: &str| CardSet::from(&cards.parse::<Pile<card_games_lib::Card>>().unwrap());
+    let state = SomeState::Playing(state);
+    assert_eq!(state.prikup(), Some(set("QC JC 9C")));
+    let passed = state.passed().unwrap();
+    assert_eq!(passed[Player::A], set("JS"));
+    assert_eq!(passed[Player::B], set("KS"));
+    assert!(passed[Player::C].is_empty());
+
     Ok(())
 }
+
+#[test]
+fn play_trick() -> Result<(), Error<String, State>> {
+    use card_games_lib::{Card as C, Rank as R, Suit as S};
+
+    let mut game = Game::default();
+    let state = Playing {
+        bid_winner: Player::A,
+        hands: test_hands_1(),
+        taken: TakenTricks::default(),
+        player: Player::A,
+        trump: None,
+        trick: Trick::new(Player::A),
+        pending_points: 0,
+        bid: 100,
+        prikup: CardSet::EMPTY,
+        passed: Seats::default(),
+    };
+
+    assert_eq!(state.legal_cards().len(), 7);
+    let state: Playing = state.step(&mut game, C(R::Ace, S::Clubs)).this()?;
+    assert_eq!(
+        state.legal_cards().iter().collect::<Vec<_>>(),
+        [C(R::Ten, S::Clubs)]
+    );
+    let state: Playing = state.step(&mut game, C(R::Ten, S::Clubs)).this()?;
+
+    let result = state.step(&mut game, C(R::Ace, S::Spades));
+    assert!(result.1.is_err());
+    let state: Playing = result.0.left().expect("Stays in Playing");
+
+    let state: Playing = state.step(&mut game, C(R::King, S::Clubs)).this()?;
+
+    assert_eq!(state.player, Player::A);
+    assert!(state.trick.is_empty());
+    assert_eq!(state.taken.count(Player::A), 1);
+    assert_eq!(
+        state
+            .taken
+            .cards_won_by(Player::A)
+            .map(|c| c.rank().point_value())
+            .sum::<u8>(),
+        25
+    );
+
+    Ok(())
+}
+
+#[test]
+fn card_names() {
+    assert_eq!(Card(Queen, Hearts).polish_name(), "Dama kier");
+    assert_eq!(Card(Ten, Spades).to_string(), "TS");
+
+    for rank in Rank::ALL.iter() {
+        for suit in Suit::ALL.iter() {
+            let card = Card(rank.clone(), suit.clone());
+            assert_eq!(
+                card.polish_name().parse(),
+                Ok(Card(rank.clone(), suit.clone()))
+            );
+            assert_eq!(
+                card.to_string().parse(),
+                Ok(Card(rank.clone(), suit.clone()))
+            );
+        }
+    }
+
+    assert_eq!("dziewiątka TREFL".parse(), Ok(Card(Nine, Clubs)));
+    assert_eq!("10♦".parse(), Ok(Card(Ten, Diamonds)));
+    assert_eq!(
+        "2H".parse::<Card>(),
+        Err(card_games_lib::ParseError::UnknownCard("2H".to_owned()))
+    );
+}
+
+#[test]
+fn pass_missing_or_repeated_card() {
+    use card_games_lib::{Card as C, Rank as R, Suit as S};
+
+    let mut game = Game::default();
+    let state = Distrubuting {
+        bid_winner: Player::A,
+        hands: test_hands_1(),
+        bid: 100,
+        prikup: CardSet::EMPTY,
+    };
+
+    let StepResult(state, result) =
+        state.step(&mut game, (C(R::Ace, S::Hearts), C(R::Ace, S::Spades)));
+    assert_eq!(
+        result,
+        Err("Trying to pass Card(Ace, Spades), which you don't have".to_owned())
+    );
+
+    let state = state.left().expect("Stays in Distrubuting");
+    let StepResult(state, result) =
+        state.step(&mut game, (C(R::Ace, S::Hearts), C(R::Ace, S::Hearts)));
+    assert_eq!(
+        result,
+        Err("Trying to pass Card(Ace, Hearts) twice".to_owned())
+    );
+    assert_eq!(
+        state
+            .left()
+            .expect("Stays in Distrubuting")
+            .hand(&Player::A)
+            .len(),
+        7
+    );
+}
+
+#[test]
+fn seats_follow_playing_order() {
+    assert_eq!(Player::C.next(), Player::A);
+    assert_eq!(Player::A.previous(), Player::C);
+    assert_eq!(
+        Player::B.round().collect::<Vec<_>>(),
+        [Player::B, Player::C, Player::A]
+    );
+    assert_eq!(Player::all().count(), 3);
+    assert_eq!(Player::from_index(3), None);
+
+    let hands = test_hands_1();
+    assert_eq!(
+        hands
+            .iter_from(Player::C)
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>(),
+        [Player::C, Player::A, Player::B]
+    );
+    assert_eq!(hands.partnership(Player::B).count(), 1);
+}
+
+#[test]
+fn snapshot_and_compare_states() {
+    use std::collections::HashSet;
+
+    let mut game = Game::default();
+    let state = SomeState::Bidding(Bidding {
+        current_bid: (Player::A, Fives::one_hundred(), Player::B),
+        hands: test_hands_1(),
+        prikup: test_prikup_1(),
+    });
+
+    let snapshot = (state.clone(), game.clone());
+    let (state, result) = state.step(&mut game, StateInput::Bidding(None));
+    assert_eq!(result, Ok(()));
+    assert_ne!(state, snapshot.0);
+
+    let (restored, _) = snapshot;
+    assert_eq!(restored.state(), State::Bidding);
+
+    let mut seen = HashSet::new();
+    assert!(seen.insert(restored.clone()));
+    assert!(!seen.insert(restored));
+    assert!(seen.insert(state));
+    assert_eq!(game, Game::default());
+}
+
+#[test]
+fn hand_is_scored_on_entering_finished() -> Result<(), Error<String, State>> {
+    use card_games_lib::{Card as C, Rank as R, Suit as S};
+
+    let mut game = Game::default();
+    let state = Playing {
+        bid_winner: Player::A,
+        hands: Seats::from_vec(vec![
+            pile![Card(Ace, Hearts)],
+            pile![Card(Ten, Hearts)],
+            pile![Card(King, Clubs)],
+        ]),
+        taken: TakenTricks::default(),
+        player: Player::A,
+        trump: None,
+        trick: Trick::new(Player::A),
+        pending_points: 0,
+        bid: 100,
+        prikup: CardSet::EMPTY,
+        passed: Seats::default(),
+    };
+
+    let state: Playing = state.step(&mut game, C(R::Ace, S::Hearts)).this()?;
+    let state: Playing = state.step(&mut game, C(R::Ten, S::Hearts)).this()?;
+    assert_eq!(game, Game::default());
+
+    let state: Finished = state.step(&mut game, C(R::King, S::Clubs)).next()?;
+    assert_eq!(state.taken.count(Player::A), 1);
+    assert_eq!(game.points(Player::A), -100);
+    assert_eq!(game.points(Player::B), 0);
+    assert_eq!(game.points(Player::C), 0);
+
+    Ok(())
+}
+
+#[test]
+fn bid_winner_concedes() -> Result<(), Error<String, State>> {
+    let mut game = Game::default();
+    let state = AdjustingBid {
+        bid_winner: Player::B,
+        bid: Fives::one_hundred(),
+        hands: test_hands_1(),
+        prikup: CardSet::EMPTY,
+    };
+
+    let state: Finished = state.act(&mut game, actions::Concede()).next()?;
+    assert_eq!(state.bid_winner, Player::B);
+    assert_eq!(state.dealer, Player::A);
+    assert_eq!(game.points(Player::B), -100);
+    // The defenders score the card points in their hands
+    assert_eq!(game.points(Player::A), 41);
+    assert_eq!(game.points(Player::C), 34);
+
+    let state = SomeState::Bidding(Bidding {
+        current_bid: (Player::A, Fives::one_hundred(), Player::B),
+        hands: test_hands_1(),
+        prikup: test_prikup_1(),
+    });
+    let (state, result) = state.step(&mut game, StateInput::Concede());
+    assert_eq!(state.state(), State::Bidding);
+    assert_eq!(
+        result,
+        Err(Error::NotInCorrectStateError {
+            held: State::Bidding,
+            given: State::AdjustingBid,
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn finished_deals_next_hand_or_ends_game() -> Result<(), Error<String, State>> {
+    let finished = || Finished {
+        bid_winner: Player::A,
+        taken: TakenTricks::default(),
+        pending_points: 0,
+        bid: 100,
+        dealer: Player::A,
+    };
+    assert_eq!(SomeState::Finished(finished()).next_player(), Player::A);
+
+    let mut game = Game::default();
+    assert_eq!(game.dealer(), Player::C);
+    let state: Bidding = finished().step(&mut game, 7).next_to()?;
+    assert_eq!(state.hands[Player::B].len(), 7);
+    assert_eq!(game.dealer(), Player::A);
+    assert_eq!(
+        SomeState::Bidding(state).bid(),
+        Some((Player::B, 100)),
+        "The player after the dealer opens"
+    );
+    assert_eq!(
+        finished().step(&mut game, 7).next_to::<GameOver>(),
+        Err(Error::NotInCorrectStateError {
+            held: State::GameOver,
+            given: State::Bidding,
+        })
+    );
+
+    game.points[Player::C] = 1000;
+    let state: GameOver = finished().step(&mut game, 7).next_to()?;
+    assert_eq!(state.winner, Player::C);
+
+    let (state, result) = SomeState::Finished(finished()).step(&mut game, StateInput::Finished(7));
+    assert_eq!(result, Ok(()));
+    assert_eq!(state.state(), State::GameOver);
+
+    Ok(())
+}
+
+#[test]
+fn every_state_is_reachable() {
+    let graph = State::graph();
+    assert_eq!(graph.initial(), Some("Bidding"));
+    assert!(graph.unreachable().is_empty());
+
+    let concede = graph
+        .transitions
+        .iter()
+        .find(|t| t.input == "Concede")
+        .expect("AdjustingBid declares Concede");
+    assert_eq!(concede.from, "AdjustingBid");
+    assert_eq!(concede.to, ["Finished"]);
+
+    let dot = graph.to_dot();
+    assert!(dot.contains("\"Finished\" -> \"Bidding\" [label=\"Finished(u64) -> Err(String)\"];"));
+    assert!(dot.contains("\"Finished\" -> \"GameOver\" [label=\"Finished(u64) -> Err(String)\"];"));
+    assert!(graph
+        .to_mermaid()
+        .contains("Distrubuting --> Playing: Distrubuting(card_games_lib#58;#58;Card, card_games_lib#58;#58;Card) -#gt; Err(String)"));
+}